@@ -1,27 +1,27 @@
-use std::{error::Error, path::Path, io, io::prelude::*, fs, process::Command, thread, time, cmp, sync::Mutex, sync::Arc};
+use std::{collections::{BTreeSet, HashMap, VecDeque}, path::{Path, PathBuf, MAIN_SEPARATOR}, io, fs};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    cursor,
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
-    backend::{Backend, CrosstermBackend},
+    backend::{Backend, CrosstermBackend, TestBackend},
+    buffer::Buffer,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    symbols,
-    text::{Span, Spans},
-    widgets::canvas::{Canvas, Context, Line, Map, MapResolution, Rectangle, Points, Painter},
-    widgets::{
-        Axis, BarChart, Block, Borders, BorderType, Cell, Chart, Dataset, Gauge, LineGauge, List, ListItem,
-        ListState, Paragraph, Row, Sparkline, Table, TableState, Tabs, Wrap,
-    },
+    symbols::Marker,
+    text::{Span, Spans, Text},
+    widgets::canvas::{Canvas, Line, Points},
+    widgets::{Block, Borders, BorderType, Clear, List, ListItem, ListState, Paragraph, Tabs, Widget},
     Frame, Terminal,
 };
-use tobj;
 
 struct StateList<T> {
     state: ListState,
     items: Vec<T>,
+    offset: usize, //first row shown, following the scrolling the list widget keeps private
+    marked: BTreeSet<usize>, //items in the selection set
 }
 
 impl<T> StateList<T> { //interactive item list
@@ -29,13 +29,39 @@ impl<T> StateList<T> { //interactive item list
         StateList {
             state: ListState::default(),
             items,
+            offset: 0,
+            marked: BTreeSet::new(),
+        }
+    }
+
+    fn scroll(&mut self, height: usize) { //follow the list widget scrolling the selection into a view of some rows
+        self.offset = match self.state.selected() {
+            None => 0,
+            Some(selected) => {
+                let selected = selected.min(self.items.len().saturating_sub(1));
+                let offset = self.offset.min(self.items.len().saturating_sub(1));
+                if selected >= offset + height {
+                    selected + 1 - height
+                } else {
+                    offset.min(selected)
+                }
+            }
+        };
+    }
+
+    fn click(&mut self, row: usize) { //select the item on a row of the view
+        if self.offset + row < self.items.len() {
+            self.state.select(Some(self.offset + row));
         }
     }
 
     fn next(&mut self) {
+        if self.items.is_empty() {
+            return self.unselect();
+        }
         let i = match self.state.selected() {
             Some(i) => {
-                if i >= self.items.len() / 3 - 1 {
+                if i >= self.items.len() - 1 {
                     0
                 } else {
                     i + 1
@@ -47,10 +73,13 @@ impl<T> StateList<T> { //interactive item list
     }
 
     fn previous(&mut self) {
+        if self.items.is_empty() {
+            return self.unselect();
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.items.len() / 3 - 1
+                    self.items.len() - 1
                 } else {
                     i - 1
                 }
@@ -63,37 +92,588 @@ impl<T> StateList<T> { //interactive item list
     fn unselect(&mut self) {
         self.state.select(None);
     }
+
+    fn toggle(&mut self, index: usize) -> bool { //add an item to the selection set or take it out; true when added
+        match self.marked.remove(&index) {
+            true  => false,
+            false => self.marked.insert(index),
+        }
+    }
+
+    fn clamp(&mut self) { //keep the selection inside the list after items are removed
+        let count = self.items.len();
+        self.marked.retain(|&i| i < count);
+        match self.state.selected() {
+            Some(_) if self.items.is_empty() => self.unselect(),
+            Some(i) if i >= self.items.len() => self.state.select(Some(self.items.len() - 1)),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct Source { //original text of a statement, so unedited files are written back unchanged
+    line: usize, //line number in the source file; keeps vertices and faces in file order
+    text: String, //statement as read, including its line ending
+    prefix: Vec<String>, //unparsed lines (comments, groups, smoothing, materials, texcoords, normals) before it
+}
+
+#[derive(Clone, PartialEq)]
+struct Vertex {
+    position: [f32; 3],
+    source: Option<Source>,
+}
+
+impl Vertex {
+    fn new(position: [f32; 3]) -> Vertex {
+        Vertex { position, source: None }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct Face { //polygon with any number of corners
+    vertices: Vec<u32>, //indices into the model's vertices
+    texcoords: Vec<u32>, //indices into the file's vt statements; empty when unused
+    normals: Vec<u32>, //indices into the file's vn statements; empty when unused
+    source: Option<Source>,
+}
+
+impl Face {
+    fn new(vertices: Vec<u32>) -> Face {
+        Face { vertices, texcoords: Vec::new(), normals: Vec::new(), source: None }
+    }
+}
+
+struct Model { //one object of an obj file
+    name: String,
+    header: Option<Source>, //`o` statement the model was read from
+    vertices: Vec<Vertex>, //empty while the model is being edited in the app's lists
+    faces: Vec<Face>,
+    trailer: Vec<String>, //unparsed lines after the last statement of the file
+}
+
+impl Model {
+    fn new(name: &str) -> Model {
+        Model {
+            name: name.to_string(),
+            header: None,
+            vertices: Vec::new(),
+            faces: Vec::new(),
+            trailer: Vec::new(),
+        }
+    }
+}
+
+struct Entry { //row of the file browser
+    name: String,
+    directory: bool,
+    size: u64, //bytes; unused for directories
+}
+
+const EXTENSIONS: [&str; 2] = ["obj", "stl"]; //file types open_file reads
+
+const UNDO_LIMIT: usize = 1000; //undo steps kept before the oldest is dropped
+
+enum Change { //single reversible edit of the displayed model
+    MoveVertex(usize, [f32; 3]),
+    SetVertex(usize, Vertex),
+    InsertVertex(usize, Vertex),
+    RemoveVertex(usize),
+    SetFace(usize, Face),
+    InsertFace(usize, Face),
+    RemoveFace(usize),
+}
+
+struct Step { //one undo or redo step
+    model: usize, //model the changes were made to
+    changes: Vec<Change>, //changes reverting the step, in the order they were made
+}
+
+enum Transform { //numeric edit applied to a set of vertices
+    Translate([f64; 3]),
+    Rotate([f64; 3], f64), //unit axis and angle in radians
+    Scale([f64; 3]),
+}
+
+#[derive(Clone, Copy)]
+struct Camera { //everything deciding what a viewport shows; angles in radians
+    yaw: f64, //turn about the vertical axis
+    pitch: f64, //tilt about the horizontal screen axis; positive looks down on the model
+    roll: f64, //turn about the viewing direction
+    target: [f64; 3], //model space point the view turns about
+    pan: [f64; 2], //screen space offset of the view centre
+    zoom: f64, //half-width of the view in model units
+    zoom_step: f64, //zoom factor of one key press
+    perspective: bool, //shrink distant geometry instead of projecting flat
+    fov: f64, //perspective field of view across the viewport width
+    distance: f64, //perspective distance from the eye to the centre of the view
+    near: f64, //perspective depth in front of the eye below which geometry is clipped
+    framing: Option<([f64; 3], [f64; 3])>, //box to zoom to at the next draw, once the viewport size is known
+}
+
+impl Default for Camera {
+    fn default() -> Camera {
+        Camera {
+            yaw: 0.0,
+            pitch: 0.0,
+            roll: 0.0,
+            target: [0.0; 3],
+            pan: [0.0, 0.0],
+            zoom: 10.0,
+            zoom_step: 1.1,
+            perspective: false,
+            fov: 60f64.to_radians(),
+            distance: 20.0,
+            near: 0.1,
+            framing: None,
+        }
+    }
+}
+
+impl Camera {
+    fn matrix(&self) -> [[f64; 3]; 3] { //world to view rotation, applying yaw, then pitch, then roll
+        let (sy, cy) = self.yaw.sin_cos();
+        let (sp, cp) = self.pitch.sin_cos();
+        let (sr, cr) = self.roll.sin_cos();
+        let yaw = [[cy, 0.0, sy], [0.0, 1.0, 0.0], [-sy, 0.0, cy]];
+        let pitch = [[1.0, 0.0, 0.0], [0.0, cp, -sp], [0.0, sp, cp]];
+        let roll = [[cr, -sr, 0.0], [sr, cr, 0.0], [0.0, 0.0, 1.0]];
+        multiply(&roll, &multiply(&pitch, &yaw))
+    }
+
+    fn with_view(name: &str) -> Camera {
+        let mut camera = Camera::default();
+        camera.set_view(name);
+        camera
+    }
+
+    fn set_view(&mut self, name: &str) -> Option<&'static str> { //turn to a named view, returning its name
+        let &(name, _, yaw, pitch) = find_view(name)?;
+        self.yaw = yaw.to_radians();
+        self.pitch = pitch.to_radians();
+        self.roll = 0.0;
+        Some(name)
+    }
+
+    fn view_name(&self) -> Option<&'static str> { //name of the preset view the camera is in, if any
+        use std::f64::consts::{PI, TAU};
+        let same = |angle: f64, degrees: f64| ((angle - degrees.to_radians() + PI).rem_euclid(TAU) - PI).abs() < 1e-6;
+        VIEWS.iter().find(|&&(_, _, yaw, pitch)| same(self.yaw, yaw) && same(self.pitch, pitch) && same(self.roll, 0.0)).map(|view| view.0)
+    }
+
+    fn bounds(&self, area: Rect) -> ([f64; 2], [f64; 2]) { //canvas x and y bounds, keeping cells about twice as tall as wide
+        let x_zoom = self.zoom;
+        let y_zoom = self.zoom * 2.0 * area.height as f64 / area.width.max(1) as f64;
+        ([self.pan[0] - x_zoom, self.pan[0] + x_zoom], [self.pan[1] - y_zoom, self.pan[1] + y_zoom])
+    }
+
+    fn fit(&mut self, (min, max): ([f64; 3], [f64; 3]), area: Rect) { //zoom so a box about the target fills the view
+        let matrix = self.matrix();
+        let mut extent = [0.0f64; 3];
+        for corner in 0..8 {
+            let p = [0, 1, 2].map(|i| if corner >> i & 1 == 0 { min[i] } else { max[i] } - self.target[i]);
+            let v = rotate(&matrix, p);
+            extent = [0, 1, 2].map(|i| extent[i].max(v[i].abs()));
+        }
+        let (x_bounds, y_bounds) = self.bounds(area);
+        let aspect = (y_bounds[1] - y_bounds[0]) / (x_bounds[1] - x_bounds[0]);
+        self.zoom = extent[0].max(extent[1] / aspect) * FIT_MARGIN;
+        self.distance = self.zoom / (self.fov / 2.0).tan() + extent[2];
+    }
+
+    fn canvas_point(&self, area: Rect, column: u16, row: u16) -> (f64, f64) { //canvas position of the middle of a terminal cell
+        let inner = Block::default().borders(Borders::ALL).inner(area);
+        let (x_bounds, y_bounds) = self.bounds(area);
+        (
+            x_bounds[0] + (column as f64 - inner.x as f64 + 0.5) / inner.width.max(1) as f64 * (x_bounds[1] - x_bounds[0]),
+            y_bounds[1] - (row as f64 - inner.y as f64 + 0.5) / inner.height.max(1) as f64 * (y_bounds[1] - y_bounds[0]),
+        )
+    }
+
+    fn zoom_at(&mut self, (x, y): (f64, f64), zoom_in: bool) { //zoom keeping the canvas point under the cursor in place
+        let growth = if zoom_in { self.zoom_step } else { 1.0 / self.zoom_step };
+        //flat views shrink the bounds about the pan, perspective views grow the model about the target
+        self.pan = match self.perspective {
+            true  => [growth * x - x + self.pan[0], growth * y - y + self.pan[1]],
+            false => [x - (x - self.pan[0]) / growth, y - (y - self.pan[1]) / growth],
+        };
+        if zoom_in {
+            self.zoom_in();
+        } else {
+            self.zoom_out();
+        }
+    }
+
+    fn move_by(&mut self, x: f64, y: f64) { //pan by fractions of the view width
+        self.pan[0] += x * self.zoom;
+        self.pan[1] += y * self.zoom;
+    }
+
+    fn zoom_in(&mut self) { //moves the eye closer in perspective
+        match self.perspective {
+            true  => self.distance /= self.zoom_step,
+            false => self.zoom /= self.zoom_step,
+        }
+    }
+
+    fn zoom_out(&mut self) {
+        match self.perspective {
+            true  => self.distance *= self.zoom_step,
+            false => self.zoom *= self.zoom_step,
+        }
+    }
+
+    fn set_perspective(&mut self, perspective: bool) { //keep the centre of the view the same size across the switch
+        let half_fov = (self.fov / 2.0).tan();
+        match (self.perspective, perspective) {
+            (true, false) => self.zoom = self.distance * half_fov,
+            (false, true) => self.distance = self.zoom / half_fov,
+            _ => {}
+        }
+        self.perspective = perspective;
+    }
+
+    fn depth(&self, v: [f64; 3]) -> f64 { //distance in front of the perspective eye
+        self.distance - v[2]
+    }
+
+    fn screen(&self, v: [f64; 3]) -> (f64, f64) { //canvas position of a view space point in front of the near plane
+        match self.perspective {
+            true => {
+                let scale = self.zoom / (self.depth(v) * (self.fov / 2.0).tan());
+                (v[0] * scale, v[1] * scale)
+            },
+            false => (v[0], v[1]),
+        }
+    }
+
+    fn project(&self, v: [f64; 3]) -> Option<(f64, f64)> { //canvas position; none behind the near plane
+        match self.perspective && self.depth(v) < self.near {
+            true => None,
+            false => Some(self.screen(v)),
+        }
+    }
+
+    fn project_edge(&self, a: [f64; 3], b: [f64; 3]) -> Option<Segment> { //canvas end points of an edge cut off at the near plane
+        let (a, b) = self.clip_edge(a, b)?;
+        Some((self.screen(a), self.screen(b)))
+    }
+
+    fn clip_edge(&self, a: [f64; 3], b: [f64; 3]) -> Option<([f64; 3], [f64; 3])> { //view space end points of the part of an edge in front of the near plane
+        let (mut a, mut b) = (a, b);
+        if self.perspective {
+            let (da, db) = (self.depth(a), self.depth(b));
+            if da < self.near && db < self.near {
+                return None;
+            }
+            let cut = |from: [f64; 3], to: [f64; 3], df: f64, dt: f64| {
+                let t = (self.near - df) / (dt - df);
+                [0, 1, 2].map(|i| from[i] + (to[i] - from[i]) * t)
+            };
+            if da < self.near {
+                a = cut(a, b, da, db);
+            } else if db < self.near {
+                b = cut(b, a, db, da);
+            }
+        }
+        Some((a, b))
+    }
+
+    fn eye_direction(&self, v: [f64; 3]) -> [f64; 3] { //view space direction from a point towards the eye
+        match self.perspective {
+            true  => sub([0.0, 0.0, self.distance], v),
+            false => [0.0, 0.0, 1.0],
+        }
+    }
+
+    fn project_polygon(&self, corners: &[[f64; 3]]) -> Vec<[f64; 3]> { //canvas x, y and nearness of the corners left after cutting at the near plane
+        let mut kept = corners.to_vec();
+        if self.perspective {
+            kept.clear();
+            for (i, &a) in corners.iter().enumerate() {
+                let b = corners[(i + 1) % corners.len()];
+                let (da, db) = (self.depth(a), self.depth(b));
+                if da >= self.near {
+                    kept.push(a);
+                }
+                if (da < self.near) != (db < self.near) {
+                    let t = (self.near - da) / (db - da);
+                    kept.push([0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t));
+                }
+            }
+        }
+        kept.iter().map(|&v| self.project_point(v)).collect()
+    }
+
+    fn project_point(&self, v: [f64; 3]) -> [f64; 3] { //canvas x, y and nearness of a point in front of the near plane
+        let (x, y) = self.screen(v);
+        //nearness must change linearly across the screen: depth does flat, its inverse in perspective
+        let nearness = match self.perspective {
+            true  => 1.0 / self.depth(v),
+            false => v[2],
+        };
+        [x, y, nearness]
+    }
+}
+
+fn multiply(a: &[[f64; 3]; 3], b: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let mut m = [[0.0; 3]; 3];
+    for (row, values) in m.iter_mut().enumerate() {
+        for (column, value) in values.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[row][k] * b[k][column]).sum();
+        }
+    }
+    m
 }
 
-enum AppMode { //app states
-    VertexList,
-    FaceList,
-    Help,
+fn rotate(matrix: &[[f64; 3]; 3], p: [f64; 3]) -> [f64; 3] { //view space position: x right, y up, z towards the viewer
+    matrix.map(|row| row[0] * p[0] + row[1] * p[1] + row[2] * p[2])
 }
 
+type Segment = ((f64, f64), (f64, f64)); //canvas end points of a line
+
+const ROTATE_STEP: f64 = std::f64::consts::PI / 36.0; //5 degrees per key press
+const VIEWS: [(&str, char, f64, f64); 7] = [ //named views: alt key, yaw and pitch in degrees
+    ("Front", '5', 0.0, 0.0),
+    ("Back", '0', 180.0, 0.0),
+    ("Left", '4', 90.0, 0.0),
+    ("Right", '6', -90.0, 0.0),
+    ("Top", '8', 0.0, 90.0),
+    ("Bottom", '2', 0.0, -90.0),
+    ("Isometric", '9', -45.0, 35.264389682754654), //atan(1/sqrt(2)), so the three axes look equally long
+];
+
+fn find_view(name: &str) -> Option<&'static (&'static str, char, f64, f64)> { //named view in any case, also as side for right and iso for isometric
+    let name = match name {
+        "side" => "right",
+        "iso" => "isometric",
+        name => name,
+    };
+    VIEWS.iter().find(|view| view.0.eq_ignore_ascii_case(name))
+}
+const LIGHT: [f64; 3] = [-0.3, 0.5, 0.8]; //view space direction towards the light of the solid render; above left of the viewer
+const AMBIENT: f64 = 0.2; //light reaching faces turned away from the light
+const SHADES: &[u8] = b".:-=+*#%@"; //ascii solid render, darkest to brightest
+const HIDDEN_TOLERANCE: f64 = 0.02; //share of the model's depth an edge may sit behind a face and still show
+const CREASE_ANGLE: f64 = 30.0; //default degrees between faces for their shared edge to show with hidden lines removed
+const ORBIT_STEP: f64 = std::f64::consts::PI / 90.0; //2 degrees per cell dragged
+const PICK_RADIUS: f64 = 3.0; //cells from a click within which a vertex is picked
+const PAN_STEP: f64 = 0.05; //fraction of the view width moved per key press
+const FIT_MARGIN: f64 = 1.1; //room left around a framed model
+const FOV_RANGE: [f64; 2] = [5.0, 170.0]; //perspective field of view limits in degrees
+
 enum StatusMode { //command states
     Normal,
     Open,
+    Write,
+    Overwrite, //waiting for confirmation to replace an existing file
+    StlFormat, //waiting for the choice between ascii and binary stl
+    NewVertex,
+    NewFace,
+    Transform,
+    Command, //typing a line for the command registry after :
+    Browse, //choosing a file to open from the popup browser
+}
+
+impl StatusMode {
+    fn command(&self) -> Option<&'static str> { //registry command given the text typed at a prompt
+        match self {
+            StatusMode::Open => Some("open"),
+            StatusMode::Write => Some("write"),
+            StatusMode::NewVertex => Some("vertex"),
+            StatusMode::NewFace => Some("face"),
+            StatusMode::Transform => Some("transform"),
+            _ => None,
+        }
+    }
+}
+
+struct Command { //named command of the : command line
+    name: &'static str,
+    usage: &'static str,
+    arguments: &'static [&'static str], //completions for the first argument
+    paths: bool, //the argument is a file path, completed from the filesystem
+    run: fn(&mut App, &str) -> Result<(), String>, //given the text after the name; sets the status itself on success
+}
+
+const COMMANDS: &[Command] = &[
+    Command { name: "open", usage: "open PATH", arguments: &[], paths: true, run: |app, args| match args {
+        "" => Err("needs a path".to_string()),
+        path => {
+            app.open_file(path);
+            Ok(())
+        },
+    }},
+    Command { name: "write", usage: "write [PATH]", arguments: &[], paths: true, run: |app, args| match (args, app.path.to_string()) {
+        ("", path) if path.is_empty() => Err("needs a path".to_string()),
+        ("", _) if app.marked_count() > 0 => Err("needs a path to write the selection to".to_string()),
+        ("", path) => {
+            app.request_write(&path);
+            Ok(())
+        },
+        (path, _) => {
+            app.request_write(path);
+            Ok(())
+        },
+    }},
+    Command { name: "browse", usage: "browse [DIRECTORY]", arguments: &[], paths: true, run: |app, args| {
+        app.open_browser(if args.is_empty() { "." } else { args });
+        Ok(())
+    }},
+    Command { name: "quit", usage: "quit", arguments: &[], paths: false, run: |app, _| {
+        app.quit = true;
+        Ok(())
+    }},
+    Command { name: "goto", usage: "goto [v|f] NUMBER", arguments: &["v", "f"], paths: false, run: |app, args| app.goto(args) },
+    Command { name: "vertex", usage: "vertex X Y Z", arguments: &[], paths: false, run: |app, args| app.add_vertex(args) },
+    Command { name: "face", usage: "face V1 V2 V3 ...", arguments: &[], paths: false, run: |app, args| app.add_face(args) },
+    Command { name: "delete", usage: "delete", arguments: &[], paths: false, run: |app, _| app.delete_item() },
+    Command { name: "transform", usage: "transform t X Y Z | r AXIS DEGREES | s FACTOR", arguments: &["translate", "rotate", "scale"], paths: false, run: |app, args| app.transform(args) },
+    Command { name: "translate", usage: "translate X Y Z", arguments: &[], paths: false, run: |app, args| app.transform(&format!("t {}", args)) },
+    Command { name: "rotate", usage: "rotate x|y|z|X Y Z DEGREES", arguments: &["x", "y", "z"], paths: false, run: |app, args| app.transform(&format!("r {}", args)) },
+    Command { name: "scale", usage: "scale FACTOR | X Y Z", arguments: &[], paths: false, run: |app, args| app.transform(&format!("s {}", args)) },
+    Command { name: "undo", usage: "undo", arguments: &[], paths: false, run: |app, _| {
+        app.restore();
+        Ok(())
+    }},
+    Command { name: "redo", usage: "redo", arguments: &[], paths: false, run: |app, _| {
+        app.redo();
+        Ok(())
+    }},
+    Command { name: "view", usage: "view front|back|left|right|top|bottom|isometric", arguments: &["front", "back", "left", "right", "top", "bottom", "isometric"], paths: false, run: |app, args| {
+        if find_view(args).is_none() {
+            return Err(format!("unknown view {}", args));
+        }
+        app.set_view(args);
+        Ok(())
+    }},
+    Command { name: "frame", usage: "frame [all|selection]", arguments: &["all", "selection"], paths: false, run: |app, args| {
+        match args {
+            "" | "all" => app.frame_all(),
+            "selection" => app.frame_selection(),
+            other => return Err(format!("unknown target {}", other)),
+        }
+        Ok(())
+    }},
+    Command { name: "set", usage: "set OPTION [VALUE]", arguments: SETTINGS, paths: false, run: |app, args| app.set_option(args) },
+    Command { name: "help", usage: "help", arguments: &[], paths: false, run: |app, _| {
+        let names: Vec<&str> = COMMANDS.iter().map(|command| command.name).collect();
+        app.status = format!("Commands: {}", names.join(", "));
+        Ok(())
+    }},
+];
+
+const SETTINGS: &[&str] = &["zoom", "zoom-step", "yaw", "pitch", "roll", "fov", "distance", "near", "crease", "perspective", "solid", "hidden-lines", "quad"]; //options of the set command
+
+fn find_command(name: &str) -> Result<&'static Command, String> { //command with a name, or the only one starting with it
+    if let Some(command) = COMMANDS.iter().find(|command| command.name == name) {
+        return Ok(command);
+    }
+    let found: Vec<&Command> = COMMANDS.iter().filter(|command| command.name.starts_with(name)).collect();
+    match found[..] {
+        [command] => Ok(command),
+        [] => Err(format!("Unknown command: {}", name)),
+        _ => Err(format!("Ambiguous command: {} ({})", name, found.iter().map(|command| command.name).collect::<Vec<_>>().join(", "))),
+    }
+}
+
+fn completions(line: &str) -> (usize, Vec<String>) { //where the word being typed starts and the words it could become; a trailing space marks a finished word
+    let finished = |word: &str| format!("{} ", word);
+    match line.split_once(' ') {
+        None => (0, COMMANDS.iter().map(|command| command.name).filter(|name| name.starts_with(line)).map(finished).collect()),
+        Some((name, argument)) => match find_command(name) {
+            Ok(command) if command.paths => (name.len() + 1, path_completions(argument)),
+            Ok(command) if !argument.contains(' ') => {
+                (name.len() + 1, command.arguments.iter().filter(|word| word.starts_with(argument)).map(|word| finished(word)).collect())
+            },
+            _ => (line.len(), Vec::new()),
+        },
+    }
+}
+
+fn path_completions(typed: &str) -> Vec<String> { //directories and readable files whose paths start with the typed one
+    let (directory, start) = match typed.rfind(['/', MAIN_SEPARATOR]) {
+        Some(i) => typed.split_at(i + 1),
+        None => ("", typed),
+    };
+    let Ok(entries) = read_entries(Path::new(if directory.is_empty() { "." } else { directory })) else { return Vec::new() };
+    entries.iter()
+        .filter(|entry| entry.name != ".." && entry.name.starts_with(start))
+        .filter(|entry| !entry.name.starts_with('.') || start.starts_with('.'))
+        .map(|entry| match entry.directory {
+            true  => format!("{}{}/", directory, entry.name),
+            false => format!("{}{}", directory, entry.name),
+        })
+        .collect()
+}
+
+fn read_entries(directory: &Path) -> io::Result<Vec<Entry>> { //parent, subdirectories, then files open_file reads, each by name
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let Ok(metadata) = fs::metadata(entry.path()) else { continue }; //follows links; broken ones are left out
+        let name = entry.file_name().to_string_lossy().to_string();
+        let readable = Path::new(&name).extension().is_some_and(|e| EXTENSIONS.iter().any(|x| e.eq_ignore_ascii_case(x)));
+        if metadata.is_dir() || readable {
+            entries.push(Entry { name, directory: metadata.is_dir(), size: metadata.len() });
+        }
+    }
+    entries.sort_by(|a, b| b.directory.cmp(&a.directory).then_with(|| a.name.cmp(&b.name)));
+    if directory.parent().is_some() {
+        entries.insert(0, Entry { name: "..".to_string(), directory: true, size: 0 });
+    }
+    Ok(entries)
+}
+
+fn format_size(bytes: u64) -> String { //file size for people, in the largest unit under 1024
+    let mut size = bytes as f64;
+    for unit in ["B", "KB", "MB", "GB"] {
+        if size < 1024.0 || unit == "GB" {
+            return match unit {
+                "B" => format!("{} {}", bytes, unit),
+                _ => format!("{:.1} {}", size, unit),
+            };
+        }
+        size /= 1024.0;
+    }
+    unreachable!()
 }
 
 struct App<'a> {
-    pub mode: AppMode,
-    
-    models: StateList<tobj::Model>, //list of loaded models (not yet used)
+    models: StateList<Model>, //list of loaded models
+
+    vertices: StateList<Vertex>, //vertices of the displayed model
 
-    vertices: StateList<f32>, //list of vertex coordinates
+    faces: StateList<Face>, //faces of the displayed model
+    picked: Vec<u32>, //vertices picked as corners of the next new face
 
-    faces: StateList<u32>, //list of vertex indices forming triangular faces
+    history: VecDeque<Step>, //undo steps
+    future: Vec<Step>, //redo steps
 
+    path: String, //file the current models were read from
     input: String, //used for commands
     status: String, //used for user feedback
     status_mode: StatusMode, //current command state
+    commands: Vec<String>, //command lines entered, oldest first
+    command_index: usize, //entry of commands shown while browsing; its length when not browsing
+    quit: bool, //set to leave the main loop
+    cursor: usize, //characters between the prompt cursor and the end of the typed text
+    browser: StateList<Entry>, //contents of the directory shown by the file browser
+    browser_dir: PathBuf,
     
-    rotation_offset: f64, //rotation tick for rendering
-    x_offset: f64, //viewport translation
-    y_offset: f64,
-    zoom: f64, //scaled bounds of viewport
-    top_down: bool, //view model from top
+    camera: Camera, //view shared by the dot and wireframe renders
+    panes: [Camera; 4], //cameras of the quad view; the controlled one is held in camera
+    pane: usize, //quad view pane the keyboard controls
+    quad_view: bool, //show front, top, side and free panes at once
+    solid: bool, //fill and shade faces instead of drawing points or edges
+    pane_areas: Vec<(usize, Rect)>, //where each pane was last drawn, for the mouse
+    list_area: Rect, //where the vertex or face list was last drawn
+    drag: Option<(u16, u16)>, //last mouse position while a button is held in the viewport
+    dragged: bool, //whether the held button has moved since it was pressed; a click otherwise
+    selection_box: Option<[u16; 4]>, //cells where a box selection was started and is now dragged to
+    hidden_lines: bool, //draw only the outline and creases of the wireframe, leaving out covered edges
+    crease_angle: f64, //radians between two faces for their shared edge to count as a crease
+    marker: Marker, //canvas symbols; braille unless ascii output was asked for
 
     pub tab_titles: Vec<&'a str>,
     pub tab_index: usize,
@@ -102,80 +682,76 @@ struct App<'a> {
 impl<'a> App<'a> {
     fn new() -> App<'a> {
         //initialize with hard-coded cube 
-        let mut cube_mesh = tobj::Mesh {
-            positions: Vec::new(),
-            normals: Vec::new(),
-            texcoords: Vec::new(),
-            indices: Vec::new(),
-            normal_indices: Vec::new(),
-            texcoord_indices: Vec::new(),
-            face_arities: Vec::new(),
-            vertex_color: Vec::new(),
-            material_id: None,
-        };
-        cube_mesh.positions = vec![
-            1.0, 1.0, 1.0, 
-            -1.0, 1.0, 1.0, 
-            -1.0, 1.0, -1.0, 
-            1.0, 1.0, -1.0, 
-            1.0, -1.0, 1.0, 
-            -1.0, -1.0, 1.0, 
-            -1.0, -1.0, -1.0, 
-            1.0, -1.0, -1.0,
+        let positions = [
+            [1.0, 1.0, 1.0],
+            [-1.0, 1.0, 1.0],
+            [-1.0, 1.0, -1.0],
+            [1.0, 1.0, -1.0],
+            [1.0, -1.0, 1.0],
+            [-1.0, -1.0, 1.0],
+            [-1.0, -1.0, -1.0],
+            [1.0, -1.0, -1.0],
         ];
-        cube_mesh.indices = vec![
-            0, 1, 2,
-            0, 2, 3,
-            4, 5, 6,
-            4, 6, 7,
-            0, 1, 5,
-            0, 4, 5,
-            1, 2, 6,
-            1, 6, 5,
-            2, 3, 7,
-            2, 7, 6,
-            3, 0, 4,
-            3, 7, 4,
+        let indices = [
+            [0, 2, 1],
+            [0, 3, 2],
+            [4, 5, 6],
+            [4, 6, 7],
+            [0, 1, 5],
+            [0, 5, 4],
+            [1, 2, 6],
+            [1, 6, 5],
+            [2, 3, 7],
+            [2, 7, 6],
+            [3, 0, 4],
+            [3, 4, 7],
         ];
-        let mut cube = tobj::Model::new(cube_mesh, "cube".to_string());
+        let cube = Model::new("cube");
         
-        App { //default values
-            mode: AppMode::Help,
+        let mut app = App { //default values
+            vertices: StateList::with_items(positions.iter().map(|&p| Vertex::new(p)).collect()),
 
-            vertices: StateList::with_items(cube.mesh.positions.clone()),
-
-            faces: StateList::with_items(cube.mesh.indices.clone()),
+            faces: StateList::with_items(indices.iter().map(|f| Face::new(f.to_vec())).collect()),
             
             models: StateList::with_items(vec![cube]),
+            picked: Vec::new(),
+
+            history: VecDeque::new(),
+            future: Vec::new(),
 
+            path: "".to_string(),
             input: "".to_string(),
             status: "Welcome to tui_obj!".to_string(),
             status_mode: StatusMode::Normal,
-
-            rotation_offset: 0.0,
-            x_offset: 0.0,
-            y_offset: 0.0,
-            zoom: 10.0,
-            top_down: false,
+            commands: Vec::new(),
+            command_index: 0,
+            quit: false,
+            cursor: 0,
+            browser: StateList::with_items(Vec::new()),
+            browser_dir: PathBuf::new(),
+
+            camera: Camera::default(),
+            panes: [Camera::with_view("Front"), Camera::with_view("Top"), Camera::with_view("Right"), Camera::default()],
+            pane: 3,
+            quad_view: false,
+            solid: false,
+            pane_areas: Vec::new(),
+            list_area: Rect::default(),
+            drag: None,
+            dragged: false,
+            selection_box: None,
+            hidden_lines: false,
+            crease_angle: CREASE_ANGLE.to_radians(),
+            marker: Marker::Braille,
             
             tab_titles: vec!["Vertex", "Face", "Help"],
             tab_index: 2,
-        }
-    }
-
-    pub fn next_tab(&mut self) { //app control functions
-        self.tab_index = (self.tab_index + 1) % self.tab_titles.len();
-    }
-
-    pub fn prev_tab(&mut self) {
-        if self.tab_index > 0 {
-            self.tab_index -= 1;
-        } else {
-            self.tab_index = self.tab_titles.len() - 1;
-        }
+        };
+        app.frame(&(0..app.vertices.items.len()).collect::<Vec<_>>());
+        app
     }
 
-    pub fn set_tab(&mut self, tab: usize) {
+    pub fn set_tab(&mut self, tab: usize) { //app control functions
         self.tab_index = tab;
         
         self.status = format!("Switched to {} Mode", self.tab_titles[tab]);
@@ -197,107 +773,1673 @@ impl<'a> App<'a> {
         }
     }
 
-    pub fn next_model(&mut self) { //model selection functions; not yet used
+    pub fn unselect_item(&mut self) { //also empties the selection set
+        match self.tab_index {
+            0 => {
+                self.vertices.unselect();
+                self.vertices.marked.clear();
+            },
+            1 => {
+                self.faces.unselect();
+                self.faces.marked.clear();
+            },
+            _ => {}
+        }
+    }
+
+    pub fn mark_item(&mut self) { //toggle the selected item in the selection set
+        let (list, kind) = match self.tab_index {
+            0 => (self.vertices.state.selected().map(|i| self.vertices.toggle(i)), "vertices"),
+            1 => (self.faces.state.selected().map(|i| self.faces.toggle(i)), "faces"),
+            _ => return,
+        };
+        let count = self.marked_count();
+        self.status = match list {
+            Some(true)  => format!("Added to selection ({} {})", count, kind),
+            Some(false) => format!("Removed from selection ({} {})", count, kind),
+            None => "Nothing selected".to_string(),
+        };
+    }
 
+    fn marked_count(&self) -> usize { //size of the selection set of the current tab
+        match self.tab_index {
+            0 => self.vertices.marked.len(),
+            1 => self.faces.marked.len(),
+            _ => 0,
+        }
     }
 
-    pub fn prev_model(&mut self) {
+    fn selection_model(&self) -> Option<Model> { //copy of the selection set as a model: marked vertices and the faces among them, or marked faces and their vertices
+        let faces: Vec<&Face> = match self.tab_index {
+            0 => self.faces.items.iter().filter(|face| face.vertices.iter().all(|v| self.vertices.marked.contains(&(*v as usize)))).collect(),
+            1 => self.faces.marked.iter().map(|&i| &self.faces.items[i]).collect(),
+            _ => return None,
+        };
+        let mut used: BTreeSet<usize> = faces.iter().flat_map(|face| face.vertices.iter().map(|&v| v as usize)).collect();
+        if self.tab_index == 0 {
+            used.extend(&self.vertices.marked);
+        }
+        if used.is_empty() {
+            return None;
+        }
+        let renumbered: HashMap<usize, u32> = used.iter().enumerate().map(|(new, &old)| (old, new as u32)).collect();
+        let mut model = Model::new(&self.models.items[self.active_model()].name);
+        model.vertices = used.iter().map(|&i| Vertex::new(self.vertices.items[i].position)).collect();
+        model.faces = faces.iter().map(|face| Face::new(face.vertices.iter().map(|v| renumbered[&(*v as usize)]).collect())).collect();
+        Some(model)
+    }
 
+    pub fn next_model(&mut self) { //model selection functions
+        let next = (self.active_model() + 1) % self.models.items.len();
+        self.select_model(next);
+        self.status = format!("Showing model {}", self.models.items[next].name);
     }
 
-    pub fn open_file(&mut self, path: &str) { //file read
-        let mut new_path = path.to_string();
-        if path.contains(".stl") {
-            let mut output = Command::new("python3")
-                .args(["microservice_helper.py", path])
-                .output()
-                .expect("failed to execute process");
+    pub fn prev_model(&mut self) {
+        let count = self.models.items.len();
+        let prev = (self.active_model() + count - 1) % count;
+        self.select_model(prev);
+        self.status = format!("Showing model {}", self.models.items[prev].name);
+    }
 
-            output.stdout.pop(); //remove null terminator!!
+    fn active_model(&self) -> usize { //index of the model shown in the vertex and face lists
+        self.models.state.selected().unwrap_or(0)
+    }
 
-            new_path = match std::str::from_utf8(&output.stdout) {
-                Ok(v) => v.to_string(),
-                Err(e) => panic!("Invalid UTF-8: {}", e)
-            };
-        }
+    fn select_model(&mut self, index: usize) { //hand the lists back to their model and take over another's
+        let active = self.active_model();
+        self.models.items[active].vertices = std::mem::take(&mut self.vertices.items);
+        self.models.items[active].faces = std::mem::take(&mut self.faces.items);
 
-        let obj = tobj::load_obj(Path::new(&new_path), &tobj::GPU_LOAD_OPTIONS);
-        if !obj.is_ok() {
-            self.status = format!("Failed to load file: {}", path);
-            return;
-        }
+        self.vertices = StateList::with_items(std::mem::take(&mut self.models.items[index].vertices));
+        self.faces = StateList::with_items(std::mem::take(&mut self.models.items[index].faces));
+        self.models.state.select(Some(index));
+        self.picked.clear();
+    }
 
-        let (models, materials) = obj.expect("Failed to load OBJ file");
+    pub fn open_file(&mut self, path: &str) -> bool { //file read; false when the file could not be loaded
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(e) => {
+                self.status = format!("Failed to load file: {} ({})", path, e);
+                return false;
+            }
+        };
+        let name = Path::new(path).file_stem().map_or("unnamed_object".to_string(), |s| s.to_string_lossy().to_string());
+        let is_stl = Path::new(path).extension().is_some_and(|e| e.eq_ignore_ascii_case("stl"));
+        let loaded = match is_stl {
+            true  => parse_stl(&data, &name).map(|model| vec![model]),
+            false => match String::from_utf8(data) {
+                Ok(text) => parse_obj(&text, &name),
+                Err(_) => Err("not a text file".to_string()),
+            },
+        };
+        let mut models = match loaded {
+            Ok(models) if models.iter().all(|m| m.vertices.is_empty()) => {
+                self.status = format!("Failed to load file: {} (no vertices found)", path);
+                return false;
+            },
+            Ok(models) => models,
+            Err(e) => {
+                self.status = format!("Failed to load file: {} ({})", path, e);
+                return false;
+            }
+        };
 
-        self.vertices = StateList::with_items(models[0].mesh.positions.clone());
-        self.faces = StateList::with_items(models[0].mesh.indices.clone());
+        self.vertices = StateList::with_items(std::mem::take(&mut models[0].vertices));
+        self.faces = StateList::with_items(std::mem::take(&mut models[0].faces));
 
         self.models = StateList::with_items(models);
+        self.picked.clear();
+        self.history.clear();
+        self.future.clear();
+        self.frame(&(0..self.vertices.items.len()).collect::<Vec<_>>());
         
+        //stl files are saved as obj next to the original
+        self.path = match is_stl {
+            true  => Path::new(path).with_extension("obj").to_string_lossy().to_string(),
+            false => path.to_string(),
+        };
         self.status = format!("Opened file: {}", path);
+        true
     }
 
-    pub fn write_file(&mut self) { //file write; not yet used
+    pub fn save_file(&mut self, path: &str) { //file write; picks the format from the extension
+        if Path::new(path).extension().is_some_and(|e| e.eq_ignore_ascii_case("stl")) {
+            self.status = format!("Write {} as (a)scii or (b)inary STL?", path);
+            self.input = path.to_string();
+            self.status_mode = StatusMode::StlFormat;
+        } else {
+            self.write_file(path);
+        }
+    }
 
+    pub fn write_file(&mut self, path: &str) { //writes only the selection set when there is one
+        let selection = self.selection_model();
+        let obj = match &selection {
+            Some(model) => write_obj(std::slice::from_ref(model)),
+            None => self.all_models(write_obj),
+        };
+        match fs::write(path, obj) {
+            Ok(_) if selection.is_some() => self.status = format!("Wrote selection to file: {}", path),
+            Ok(_) => {
+                self.path = path.to_string();
+                self.status = format!("Wrote file: {}", path);
+            },
+            Err(e) => self.status = format!("Failed to write file: {} ({})", path, e),
+        }
     }
 
-    pub fn new_item(&mut self) { //edit functions; not yet used
+    pub fn write_stl_file(&mut self, path: &str, binary: bool) {
+        let selection = self.selection_model();
+        let stl = match &selection {
+            Some(model) => write_stl(std::slice::from_ref(model), binary),
+            None => self.all_models(|models| write_stl(models, binary)),
+        };
+        match fs::write(path, stl) {
+            Ok(_) if selection.is_some() => self.status = format!("Wrote selection to {} STL file: {}", if binary { "binary" } else { "ascii" }, path),
+            Ok(_) => self.status = format!("Wrote {} STL file: {}", if binary { "binary" } else { "ascii" }, path), //the lossy export does not become the file w saves to
+            Err(e) => self.status = format!("Failed to write file: {} ({})", path, e),
+        }
+    }
 
+    fn all_models<T>(&mut self, read: impl FnOnce(&[Model]) -> T) -> T { //read every model, handing the edited lists back to theirs meanwhile
+        let active = self.active_model();
+        std::mem::swap(&mut self.models.items[active].vertices, &mut self.vertices.items);
+        std::mem::swap(&mut self.models.items[active].faces, &mut self.faces.items);
+        let result = read(&self.models.items);
+        std::mem::swap(&mut self.models.items[active].vertices, &mut self.vertices.items);
+        std::mem::swap(&mut self.models.items[active].faces, &mut self.faces.items);
+        result
     }
 
-    pub fn delete_item(&mut self) {
+    pub fn new_item(&mut self) { //edit functions
+        match self.tab_index {
+            0 => {
+                self.status = "".to_string();
+                self.status_mode = StatusMode::NewVertex;
+            },
+            1 => {
+                //start from the picked vertices, if any
+                let picked: Vec<String> = self.picked.iter().map(|v| (v + 1).to_string()).collect();
+                self.status = picked.join(" ");
+                self.status_mode = StatusMode::NewFace;
+            },
+            _ => {}
+        }
+    }
 
+    pub fn pick_item(&mut self) { //toggle the selected vertex as a corner of the next face
+        match self.vertices.state.selected() {
+            Some(i) => {
+                let i = i as u32;
+                match self.picked.iter().position(|&v| v == i) {
+                    Some(p) => {
+                        self.picked.remove(p);
+                        self.status = format!("Unpicked v{} ({} picked)", i + 1, self.picked.len());
+                    },
+                    None => {
+                        self.picked.push(i);
+                        self.status = format!("Picked v{} ({} picked)", i + 1, self.picked.len());
+                    },
+                }
+            },
+            None => self.status = "No vertex selected".to_string(),
+        }
     }
 
-    pub fn translate(&mut self) {
+    pub fn delete_item(&mut self) -> Result<(), String> { //deletes the selection set when there is one
+        if self.marked_count() > 0 {
+            self.delete_marked();
+            return Ok(());
+        }
+        if self.tab_index == 0 {
+            match self.vertices.state.selected() {
+                Some(i) => {
+                    self.backup();
+                    let dropped = self.remove_vertices(&BTreeSet::from([i]));
+                    self.vertices.clamp();
+                    self.faces.clamp();
+                    self.status = format!("Deleted vertex v{} and {} faces using it", i + 1, dropped);
+                },
+                None => return Err("no vertex selected".to_string()),
+            }
+        } else if self.tab_index == 1 {
+            match self.faces.state.selected() {
+                Some(i) => {
+                    self.backup();
+                    self.keep_prefix(self.faces.items[i].source.clone());
+                    self.apply(Change::RemoveFace(i));
+                    self.faces.clamp();
+                    self.status = format!("Deleted face f{}", i + 1);
+                },
+                None => return Err("no face selected".to_string()),
+            }
+        }
+        Ok(())
+    }
 
+    fn delete_marked(&mut self) { //remove every item of the selection set in one undo step
+        self.backup();
+        if self.tab_index == 0 {
+            let marked = std::mem::take(&mut self.vertices.marked);
+            let dropped = self.remove_vertices(&marked);
+            self.status = format!("Deleted {} vertices and {} faces using them", marked.len(), dropped);
+        } else {
+            let marked = std::mem::take(&mut self.faces.marked);
+            for &i in marked.iter().rev() {
+                self.keep_prefix(self.faces.items[i].source.clone());
+                self.apply(Change::RemoveFace(i));
+            }
+            self.status = format!("Deleted {} faces", marked.len());
+        }
+        self.vertices.marked.clear();
+        self.faces.marked.clear();
+        self.vertices.clamp();
+        self.faces.clamp();
     }
 
-    fn backup(&mut self) { //undo functionality functions; not yet used
+    fn add_face(&mut self, input: &str) -> Result<(), String> { //parse vertex numbers as listed and append the face
+        let numbers: Vec<u32> = input.split_whitespace().map(str::parse).collect::<Result<_, _>>().unwrap_or_default();
+        let count = self.vertices.items.len() as u32;
+        if numbers.len() < 3 || numbers.iter().any(|&n| n == 0 || n > count) {
+            return Err(format!("invalid vertices: {} (need 3 or more of v1 to v{})", input, count));
+        }
+        let vertices: Vec<u32> = numbers.iter().map(|n| n - 1).collect();
+        if (1..vertices.len()).any(|i| vertices[..i].contains(&vertices[i])) {
+            return Err(format!("invalid vertices: {} (repeated vertex)", input));
+        }
 
+        let i = self.faces.items.len();
+        self.backup();
+        self.apply(Change::InsertFace(i, Face::new(vertices)));
+        self.faces.state.select(Some(i));
+        self.picked.clear();
+        self.status = format!("Added face f{}", i + 1);
+        Ok(())
     }
 
-    fn restore(&mut self) {
+    fn add_vertex(&mut self, input: &str) -> Result<(), String> { //parse "x y z" and append the vertex
+        let values: Vec<f32> = input.split_whitespace().map(str::parse).collect::<Result<_, _>>().unwrap_or_default();
+        if values.len() != 3 {
+            return Err(format!("invalid coordinates: {}", input));
+        }
 
+        let i = self.vertices.items.len();
+        self.backup();
+        self.apply(Change::InsertVertex(i, Vertex::new([values[0], values[1], values[2]])));
+        self.vertices.state.select(Some(i));
+        self.status = format!("Added vertex v{}", i + 1);
+        Ok(())
     }
-    
-    fn zoom_in(&mut self, factor: f64) { //viewport control functions
-        self.zoom = self.zoom / 1.1
-    }
-    
-    fn zoom_out(&mut self, factor: f64) {
-        self.zoom = self.zoom * 1.1
-    }
-}
 
-fn main() -> io::Result<()> {
-    //init terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    fn remove_vertices(&mut self, indices: &BTreeSet<usize>) -> usize { //remove vertices and the faces using them, renumbering the rest in one pass
+        let mut dropped = 0;
 
-    //run terminal app
-    let app = App::new();
-    let exit_res = run(&mut terminal, app);    
+        //back to front so removals don't shift faces still to be visited
+        for i in (0..self.faces.items.len()).rev() {
+            if self.faces.items[i].vertices.iter().any(|&v| indices.contains(&(v as usize))) {
+                self.keep_prefix(self.faces.items[i].source.clone());
+                self.apply(Change::RemoveFace(i));
+                dropped += 1;
+            }
+        }
 
-    //reset terminal
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
-    terminal.show_cursor()?;
+        //new number of every vertex once the removed ones are gone; one edit per face that changes
+        let mut removed = 0;
+        let renumbered: Vec<u32> = (0..self.vertices.items.len()).map(|i| {
+            let new = (i - removed) as u32;
+            removed += indices.contains(&i) as usize;
+            new
+        }).collect();
+        for i in 0..self.faces.items.len() {
+            if self.faces.items[i].vertices.iter().any(|&v| renumbered[v as usize] != v) {
+                let mut face = self.faces.items[i].clone();
+                for v in face.vertices.iter_mut() {
+                    *v = renumbered[*v as usize];
+                }
+                self.apply(Change::SetFace(i, face));
+            }
+        }
 
-    Ok(())
-}
+        for &i in indices.iter().rev() {
+            self.keep_prefix(self.vertices.items[i].source.clone());
+            self.apply(Change::RemoveVertex(i));
+        }
+        self.picked.clear();
+        self.faces.marked.clear();
+        dropped
+    }
 
-fn run<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
-    loop {
+    fn keep_prefix(&mut self, source: Option<Source>) { //hand the unparsed lines of a statement being removed to its nearest neighbour
+        let source = match source {
+            Some(source) if !source.prefix.is_empty() => source,
+            _ => return,
+        };
+
+        //prefer the next statement in file order, else the one before
+        for after in [true, false] {
+            let vertex = nearest_source(self.vertices.items.iter().map(|v| v.source.as_ref()), source.line, after);
+            let face = nearest_source(self.faces.items.iter().map(|f| f.source.as_ref()), source.line, after);
+            let use_vertex = match (vertex, face) {
+                (Some(v), Some(f)) => (v.0 < f.0) == after,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => continue,
+            };
+
+            let join = |target: &mut Option<Source>| if let Some(target) = target {
+                let mut prefix = source.prefix.clone();
+                match after {
+                    true  => {
+                        prefix.append(&mut target.prefix);
+                        target.prefix = prefix;
+                    },
+                    false => target.prefix.append(&mut prefix),
+                }
+            };
+            if use_vertex {
+                let i = vertex.map_or(0, |v| v.1);
+                let mut vertex = self.vertices.items[i].clone();
+                join(&mut vertex.source);
+                self.apply(Change::SetVertex(i, vertex));
+            } else {
+                let i = face.map_or(0, |f| f.1);
+                let mut face = self.faces.items[i].clone();
+                join(&mut face.source);
+                self.apply(Change::SetFace(i, face));
+            }
+            return;
+        }
+    }
+
+    pub fn translate(&mut self) { //open the transform prompt
+        self.status = "".to_string();
+        self.status_mode = StatusMode::Transform;
+    }
+
+    fn transform(&mut self, input: &str) -> Result<(), String> { //apply a typed transform to the selection, or the whole model
+        let transform = parse_transform(input)?;
+
+        let (targets, scope) = match (self.tab_index, self.vertices.state.selected(), self.faces.state.selected()) {
+            (0, ..) if !self.vertices.marked.is_empty() => {
+                (self.vertices.marked.iter().copied().collect(), format!("{} vertices", self.vertices.marked.len()))
+            },
+            (1, ..) if !self.faces.marked.is_empty() => {
+                let targets: BTreeSet<usize> = self.faces.marked.iter().flat_map(|&i| self.faces.items[i].vertices.iter().map(|&v| v as usize)).collect();
+                (targets.into_iter().collect(), format!("{} faces", self.faces.marked.len()))
+            },
+            (0, Some(i), _) => (vec![i], format!("vertex v{}", i + 1)),
+            (1, _, Some(i)) => {
+                let targets = self.faces.items[i].vertices.iter().map(|&v| v as usize).collect();
+                (targets, format!("face f{}", i + 1))
+            },
+            _ => {
+                let name = &self.models.items[self.active_model()].name;
+                ((0..self.vertices.items.len()).collect(), format!("model {}", name))
+            },
+        };
+
+        //rotate and scale about the centre of the targets; a lone vertex uses the origin
+        let mut pivot = [0.0; 3];
+        if targets.len() > 1 {
+            for &i in &targets {
+                for (axis, value) in pivot.iter_mut().enumerate() {
+                    *value += self.vertices.items[i].position[axis] as f64 / targets.len() as f64;
+                }
+            }
+        }
+
+        self.backup();
+        for &i in &targets {
+            let p = self.vertices.items[i].position;
+            let d = [p[0] as f64 - pivot[0], p[1] as f64 - pivot[1], p[2] as f64 - pivot[2]];
+            let moved = match transform {
+                Transform::Translate(t) => [d[0] + t[0], d[1] + t[1], d[2] + t[2]],
+                Transform::Rotate(k, angle) => {
+                    //rodrigues' rotation formula
+                    let (sin, cos) = angle.sin_cos();
+                    let dot = k[0] * d[0] + k[1] * d[1] + k[2] * d[2];
+                    let cross = [k[1] * d[2] - k[2] * d[1], k[2] * d[0] - k[0] * d[2], k[0] * d[1] - k[1] * d[0]];
+                    let mut r = [0.0; 3];
+                    for axis in 0..3 {
+                        r[axis] = d[axis] * cos + cross[axis] * sin + k[axis] * dot * (1.0 - cos);
+                    }
+                    r
+                },
+                Transform::Scale(s) => [d[0] * s[0], d[1] * s[1], d[2] * s[2]],
+            };
+            let position = [
+                (moved[0] + pivot[0]) as f32,
+                (moved[1] + pivot[1]) as f32,
+                (moved[2] + pivot[2]) as f32,
+            ];
+            self.apply(Change::MoveVertex(i, position));
+        }
+
+        self.status = match transform {
+            Transform::Translate(_) => format!("Translated {}", scope),
+            Transform::Rotate(..) => format!("Rotated {}", scope),
+            Transform::Scale(_) => format!("Scaled {}", scope),
+        };
+        Ok(())
+    }
+
+    fn backup(&mut self) { //undo functionality functions; starts a new undo step
+        self.future.clear();
+        self.history.push_back(Step { model: self.active_model(), changes: Vec::new() });
+        if self.history.len() > UNDO_LIMIT {
+            self.history.pop_front();
+        }
+    }
+
+    fn apply(&mut self, change: Change) { //make an edit, recording its inverse in the current undo step
+        let inverse = self.swap_change(change);
+        match self.history.back_mut() {
+            Some(step) => step.changes.push(inverse),
+            None => self.history.push_back(Step { model: self.active_model(), changes: vec![inverse] }),
+        }
+    }
+
+    fn swap_change(&mut self, change: Change) -> Change { //make an edit and return the change reverting it
+        match change {
+            Change::MoveVertex(i, position) => {
+                Change::MoveVertex(i, std::mem::replace(&mut self.vertices.items[i].position, position))
+            },
+            Change::SetVertex(i, vertex) => Change::SetVertex(i, std::mem::replace(&mut self.vertices.items[i], vertex)),
+            Change::InsertVertex(i, vertex) => {
+                self.vertices.items.insert(i, vertex);
+                Change::RemoveVertex(i)
+            },
+            Change::RemoveVertex(i) => Change::InsertVertex(i, self.vertices.items.remove(i)),
+            Change::SetFace(i, face) => Change::SetFace(i, std::mem::replace(&mut self.faces.items[i], face)),
+            Change::InsertFace(i, face) => {
+                self.faces.items.insert(i, face);
+                Change::RemoveFace(i)
+            },
+            Change::RemoveFace(i) => Change::InsertFace(i, self.faces.items.remove(i)),
+        }
+    }
+
+    fn revert(&mut self, step: Step) -> Step { //apply a step last change first, returning the step that reverts it
+        if step.model != self.active_model() {
+            self.select_model(step.model);
+        }
+        let changes: Vec<Change> = step.changes.into_iter().rev().map(|change| self.swap_change(change)).collect();
+        //insertions and removals renumber items, so the selection sets no longer apply
+        if changes.iter().any(|c| matches!(c, Change::InsertVertex(..) | Change::RemoveVertex(_) | Change::InsertFace(..) | Change::RemoveFace(_))) {
+            self.vertices.marked.clear();
+            self.faces.marked.clear();
+        }
+        self.vertices.clamp();
+        self.faces.clamp();
+        self.picked.clear();
+        Step { model: step.model, changes }
+    }
+
+    fn restore(&mut self) { //undo
+        //skip steps in which nothing changed
+        while let Some(step) = self.history.pop_back() {
+            if !step.changes.is_empty() {
+                let redo = self.revert(step);
+                self.future.push(redo);
+                self.status = format!("Undid edit ({} left)", self.history.len());
+                return;
+            }
+        }
+        self.status = "Nothing to undo".to_string();
+    }
+
+    fn redo(&mut self) {
+        match self.future.pop() {
+            Some(step) => {
+                let undo = self.revert(step);
+                self.history.push_back(undo);
+                self.status = format!("Redid edit ({} left)", self.future.len());
+            },
+            None => self.status = "Nothing to redo".to_string(),
+        }
+    }
+    
+    fn command_line(&mut self) { //command line functions; open the : prompt
+        self.status = "".to_string();
+        self.command_index = self.commands.len();
+        self.status_mode = StatusMode::Command;
+    }
+
+    fn execute(&mut self, line: &str) { //run a command line through the registry
+        let line = line.trim();
+        let (name, args) = line.split_once(' ').unwrap_or((line, ""));
+        if name.is_empty() {
+            self.status = "".to_string();
+            return;
+        }
+        match find_command(name) {
+            Ok(command) => if let Err(e) = (command.run)(self, args.trim()) {
+                self.status = format!("{}: {} (usage: {})", command.name, e, command.usage);
+            },
+            Err(e) => self.status = e,
+        }
+    }
+
+    fn enter_command(&mut self) { //run the typed command line and remember it
+        let line = self.status.trim().to_string();
+        self.status_mode = StatusMode::Normal;
+        if !line.is_empty() && self.commands.last() != Some(&line) {
+            self.commands.push(line.to_string());
+        }
+        self.execute(&line);
+    }
+
+    fn browse_commands(&mut self, back: bool) { //step through the command history into the command line
+        match back {
+            true  if self.command_index > 0 => self.command_index -= 1,
+            false if self.command_index < self.commands.len() => self.command_index += 1,
+            _ => return,
+        }
+        self.status = self.commands.get(self.command_index).cloned().unwrap_or_default();
+        self.cursor = 0;
+    }
+
+    fn complete(&mut self) { //extend the command or path being typed as far as every completion agrees
+        let (start, found) = match self.status_mode {
+            StatusMode::Command => completions(&self.status),
+            _ => (0, path_completions(&self.status)),
+        };
+        let Some(first) = found.first() else { return };
+        let common = found.iter().fold(first.len(), |length, word| {
+            first.bytes().zip(word.bytes()).take(length).take_while(|(a, b)| a == b).count()
+        });
+        let common = (0..=common).rev().find(|&i| first.is_char_boundary(i)).unwrap_or(0);
+        self.status.truncate(start);
+        self.status.push_str(&first[..common]);
+        self.cursor = 0;
+    }
+
+    fn edit_prompt(&mut self, code: KeyCode) { //typing and cursor keys of the text prompts
+        let chars = self.status.chars().count();
+        let at = self.status.char_indices().nth(chars - self.cursor).map_or(self.status.len(), |(i, _)| i);
+        match code {
+            KeyCode::Char(c) => self.status.insert(at, c),
+            KeyCode::Backspace if self.cursor < chars => {
+                let before = self.status[..at].chars().next_back().map_or(0, char::len_utf8);
+                self.status.remove(at - before);
+            },
+            KeyCode::Delete if self.cursor > 0 => {
+                self.status.remove(at);
+                self.cursor -= 1;
+            },
+            KeyCode::Left => self.cursor = (self.cursor + 1).min(chars),
+            KeyCode::Right => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Home => self.cursor = chars,
+            KeyCode::End => self.cursor = 0,
+            _ => {}
+        }
+    }
+
+    fn open_browser(&mut self, directory: &str) { //file functions; show the popup browser on a directory
+        match fs::canonicalize(directory).and_then(|path| Ok((read_entries(&path)?, path))) {
+            Ok((entries, path)) => {
+                self.browser = StateList::with_items(entries);
+                self.browser.next();
+                self.browser_dir = path;
+                self.status_mode = StatusMode::Browse;
+            },
+            Err(e) => {
+                self.status_mode = StatusMode::Normal;
+                self.status = format!("Cannot browse {} ({})", directory, e);
+            },
+        }
+        self.cursor = 0;
+    }
+
+    fn browse_selected(&mut self) { //enter the highlighted directory or open the highlighted file
+        let Some(entry) = self.browser.state.selected().and_then(|i| self.browser.items.get(i)) else { return };
+        let path = self.browser_dir.join(&entry.name);
+        let path = path.to_string_lossy().to_string();
+        match entry.directory {
+            true  => self.open_browser(&path),
+            false => {
+                self.status_mode = StatusMode::Normal;
+                self.open_file(&path);
+            },
+        }
+    }
+
+    fn browse_up(&mut self) { //show the parent directory, highlighting the one left
+        let Some(parent) = self.browser_dir.parent().map(Path::to_path_buf) else { return };
+        let left = self.browser_dir.file_name().map(|name| name.to_string_lossy().to_string());
+        self.open_browser(&parent.to_string_lossy());
+        if let Some(i) = self.browser.items.iter().position(|entry| Some(&entry.name) == left.as_ref()) {
+            self.browser.state.select(Some(i));
+        }
+    }
+
+    fn request_write(&mut self, path: &str) { //write a file, asking first if it would replace one
+        if Path::new(path).exists() {
+            self.status = match self.marked_count() {
+                0 => format!("{} already exists, overwrite? (y/n)", path),
+                _ => format!("{} already exists, overwrite it with only the selection? (y/n)", path),
+            };
+            self.input = path.to_string();
+            self.status_mode = StatusMode::Overwrite;
+        } else {
+            self.save_file(path);
+        }
+    }
+
+    fn goto(&mut self, args: &str) -> Result<(), String> { //select an item by its listed number, switching tab if asked
+        let words: Vec<&str> = args.split_whitespace().collect();
+        let (tab, number) = match words[..] {
+            ["v", number] => (0, number),
+            ["f", number] => (1, number),
+            [number] if self.tab_index < 2 => (self.tab_index, number),
+            [_] => return Err("give v or f outside the vertex and face modes".to_string()),
+            _ => return Err("expected a number".to_string()),
+        };
+        let (list, kind) = match tab {
+            0 => (&mut self.vertices.state, "v"),
+            _ => (&mut self.faces.state, "f"),
+        };
+        let count = if tab == 0 { self.vertices.items.len() } else { self.faces.items.len() };
+        match number.parse::<usize>() {
+            Ok(n) if 1 <= n && n <= count => {
+                list.select(Some(n - 1));
+                self.tab_index = tab;
+                self.status = format!("Selected {}{}", kind, n);
+                Ok(())
+            },
+            _ => Err(format!("no {}{} ({}1 to {}{})", kind, number, kind, kind, count)),
+        }
+    }
+
+    fn set_option(&mut self, args: &str) -> Result<(), String> { //change a setting by name; switches take on or off, on by default
+        let (name, value) = args.split_once(' ').map_or((args, ""), |(name, value)| (name, value.trim()));
+        let number = || value.parse::<f64>().map_err(|_| format!("invalid number for {}: {}", name, value));
+        let positive = || match number()? {
+            number if number > 0.0 => Ok(number),
+            _ => Err(format!("{} must be positive", name)),
+        };
+        let switch = || match value {
+            "" | "on" | "true" => Ok(true),
+            "off" | "false" => Ok(false),
+            _ => Err(format!("{} takes on or off", name)),
+        };
+        match name {
+            "zoom" => {
+                self.camera.zoom = positive()?;
+                self.camera.framing = None;
+            },
+            "zoom-step" => match number()? {
+                step if step > 1.0 => self.camera.zoom_step = step,
+                _ => return Err("zoom step must be greater than 1".to_string()),
+            },
+            "yaw" => self.camera.yaw = number()?.to_radians(),
+            "pitch" => self.camera.pitch = number()?.to_radians(),
+            "roll" => self.camera.roll = number()?.to_radians(),
+            "fov" => match number()? {
+                fov if (FOV_RANGE[0]..=FOV_RANGE[1]).contains(&fov) => self.camera.fov = fov.to_radians(),
+                _ => return Err(format!("fov must be between {} and {} degrees", FOV_RANGE[0], FOV_RANGE[1])),
+            },
+            "distance" => {
+                self.camera.distance = positive()?;
+                self.camera.framing = None;
+            },
+            "near" => self.camera.near = positive()?,
+            "crease" => match number()? {
+                angle if (0.0..=180.0).contains(&angle) => self.crease_angle = angle.to_radians(),
+                _ => return Err("crease must be between 0 and 180 degrees".to_string()),
+            },
+            "perspective" => self.camera.set_perspective(switch()?),
+            "solid" => self.solid = switch()?,
+            "hidden-lines" => self.hidden_lines = switch()?,
+            "quad" => self.quad_view = switch()?,
+            "" => return Err("needs an option".to_string()),
+            other => return Err(format!("unknown option {}", other)),
+        }
+        self.status = match value {
+            "" => format!("Set {}", name),
+            value => format!("Set {} to {}", name, value),
+        };
+        Ok(())
+    }
+
+    fn toggle_top_view(&mut self) { //viewport control functions; switch between looking down on the model and the front
+        match self.camera.view_name() {
+            Some("Top") => self.set_view("Front"),
+            _ => self.set_view("Top"),
+        }
+    }
+
+    fn set_view(&mut self, name: &str) { //turn the camera to a named view
+        if let Some(name) = self.camera.set_view(name) {
+            self.status = format!("{} view", name);
+        }
+    }
+
+    fn mouse(&mut self, event: MouseEvent) { //mouse controls: orbit, pan and zoom in the viewport, select in the lists
+        let (column, row) = (event.column, event.row);
+        let inside = |area: Rect| area.x <= column && column < area.right() && area.y <= row && row < area.bottom();
+        let pane = self.pane_areas.iter().copied().find(|&(_, area)| inside(area));
+        let adding = event.modifiers.intersects(KeyModifiers::SHIFT | KeyModifiers::CONTROL);
+        match event.kind {
+            MouseEventKind::Down(button) => {
+                if let Some((index, _)) = pane {
+                    self.select_pane(index);
+                    self.drag = Some((column, row));
+                    self.dragged = false;
+                    if adding && button == MouseButton::Left {
+                        self.selection_box = Some([column, row, column, row]);
+                    }
+                } else if inside(self.list_area) && row > self.list_area.y && row + 1 < self.list_area.bottom() { //inside the borders
+                    let row = (row - self.list_area.y - 1) as usize;
+                    match self.tab_index {
+                        0 => self.vertices.click(row),
+                        1 => self.faces.click(row),
+                        _ => {}
+                    }
+                    if adding {
+                        self.mark_item();
+                    }
+                }
+            },
+            MouseEventKind::Drag(button) => {
+                let area = self.pane_areas.iter().find(|&&(index, _)| index == self.pane).map(|&(_, area)| area);
+                if let Some(corners) = &mut self.selection_box {
+                    corners[2] = column;
+                    corners[3] = row;
+                } else if let (Some((x, y)), Some(area)) = (self.drag, area) {
+                    let (dx, dy) = (column as f64 - x as f64, row as f64 - y as f64);
+                    match button {
+                        MouseButton::Left => {
+                            self.camera.yaw += dx * ORBIT_STEP;
+                            self.camera.pitch += dy * ORBIT_STEP;
+                        },
+                        _ => {
+                            let (from, to) = (self.camera.canvas_point(area, x, y), self.camera.canvas_point(area, column, row));
+                            self.camera.pan[0] -= to.0 - from.0;
+                            self.camera.pan[1] -= to.1 - from.1;
+                        },
+                    }
+                }
+                //only drags that started in a pane move the camera
+                if self.drag.is_some() {
+                    self.drag = Some((column, row));
+                    self.dragged = true;
+                }
+            },
+            MouseEventKind::Up(button) => {
+                let area = self.pane_areas.iter().find(|&&(index, _)| index == self.pane).map(|&(_, area)| area);
+                if let (Some(corners), Some(area)) = (self.selection_box.take(), area) {
+                    self.box_select(area, corners);
+                } else if let (Some(_), false, MouseButton::Left, Some((_, area))) = (self.drag, self.dragged, button, pane) {
+                    self.pick_at(area, column, row);
+                }
+                self.drag = None;
+            },
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+                if let Some((index, area)) = pane {
+                    self.select_pane(index);
+                    let point = self.camera.canvas_point(area, column, row);
+                    self.camera.zoom_at(point, event.kind == MouseEventKind::ScrollUp);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    fn box_select(&mut self, area: Rect, corners: [u16; 4]) { //add the vertices, or whole faces, inside a dragged box to the selection set
+        let (a, b) = (self.camera.canvas_point(area, corners[0], corners[1]), self.camera.canvas_point(area, corners[2], corners[3]));
+        let inside = |(x, y): (f64, f64)| a.0.min(b.0) <= x && x <= a.0.max(b.0) && a.1.min(b.1) <= y && y <= a.1.max(b.1);
+        let positions = view_positions(&self.camera, &self.vertices.items);
+        let boxed: Vec<bool> = positions.iter().map(|&v| self.camera.project(v).is_some_and(inside)).collect();
+        let (count, kind) = match self.tab_index {
+            0 => {
+                let before = self.vertices.marked.len();
+                self.vertices.marked.extend((0..boxed.len()).filter(|&i| boxed[i]));
+                (self.vertices.marked.len() - before, "vertices")
+            },
+            1 => {
+                let before = self.faces.marked.len();
+                let faces: Vec<usize> = (0..self.faces.items.len()).filter(|&i| self.faces.items[i].vertices.iter().all(|&v| boxed[v as usize])).collect();
+                self.faces.marked.extend(faces);
+                (self.faces.marked.len() - before, "faces")
+            },
+            _ => return,
+        };
+        self.status = format!("Added {} {} to selection ({} {})", count, kind, self.marked_count(), kind);
+    }
+
+    fn pick_at(&mut self, area: Rect, column: u16, row: u16) { //select the vertex nearest a clicked cell, or the front face under it
+        let (x, y) = self.camera.canvas_point(area, column, row);
+        let positions = view_positions(&self.camera, &self.vertices.items);
+        match self.tab_index {
+            0 => {
+                //measure in cells, as canvas units are not square
+                let (x_bounds, y_bounds) = self.camera.bounds(area);
+                let inner = Block::default().borders(Borders::ALL).inner(area);
+                let cell = ((x_bounds[1] - x_bounds[0]) / inner.width.max(1) as f64, (y_bounds[1] - y_bounds[0]) / inner.height.max(1) as f64);
+                let nearest = positions.iter().enumerate()
+                    .filter_map(|(i, &v)| self.camera.project(v).map(|(px, py)| (i, ((px - x) / cell.0).hypot((py - y) / cell.1))))
+                    .filter(|&(_, distance)| distance <= PICK_RADIUS)
+                    .min_by(|a, b| a.1.total_cmp(&b.1));
+                match nearest {
+                    Some((i, _)) => {
+                        self.vertices.state.select(Some(i));
+                        self.status = format!("Picked vertex {}", i + 1);
+                    },
+                    None => self.status = "No vertex under the cursor".to_string(),
+                }
+            },
+            1 => {
+                let front = view_triangles(&self.camera, &positions, &self.faces.items).into_iter()
+                    .filter_map(|(p, face)| {
+                        let area = (p[1][0] - p[0][0]) * (p[2][1] - p[0][1]) - (p[2][0] - p[0][0]) * (p[1][1] - p[0][1]);
+                        let w = [(1, 2), (2, 0), (0, 1)].map(|(a, b)| ((p[b][0] - p[a][0]) * (y - p[a][1]) - (x - p[a][0]) * (p[b][1] - p[a][1])) / area);
+                        (area != 0.0 && w.iter().all(|&w| w >= 0.0)).then(|| (face, w[0] * p[0][2] + w[1] * p[1][2] + w[2] * p[2][2]))
+                    })
+                    .max_by(|a, b| a.1.total_cmp(&b.1));
+                match front {
+                    Some((i, _)) => {
+                        self.faces.state.select(Some(i));
+                        self.status = format!("Picked face {}", i + 1);
+                    },
+                    None => self.status = "No face under the cursor".to_string(),
+                }
+            },
+            _ => {}
+        }
+    }
+
+    fn toggle_solid(&mut self) {
+        self.solid = !self.solid;
+        self.status = match self.solid {
+            true  => "Solid view".to_string(),
+            false => "Wireframe view".to_string(),
+        };
+    }
+
+    fn toggle_hidden_lines(&mut self) {
+        self.hidden_lines = !self.hidden_lines;
+        self.status = match self.hidden_lines {
+            true  => format!("Hidden lines removed, creases past {:.0}°", self.crease_angle.to_degrees()),
+            false => "All edges shown".to_string(),
+        };
+    }
+
+    fn widen_crease_angle(&mut self, degrees: f64) {
+        let angle = self.crease_angle.to_degrees() + degrees;
+        self.crease_angle = angle.clamp(0.0, 180.0).to_radians();
+        self.status = format!("Crease angle {:.0}°", self.crease_angle.to_degrees());
+    }
+
+    fn toggle_quad_view(&mut self) {
+        self.quad_view = !self.quad_view;
+        self.status = match self.quad_view {
+            true  => "Quad view".to_string(),
+            false => "Single view".to_string(),
+        };
+    }
+
+    fn next_pane(&mut self) { //move the keyboard controls to the next pane of the quad view
+        if !self.quad_view {
+            return;
+        }
+        let next = (self.pane + 1) % self.panes.len();
+        self.select_pane(next);
+        self.status = format!("Controlling pane {}", next + 1);
+    }
+
+    fn select_pane(&mut self, index: usize) { //hand the camera back to its pane and take over another's
+        std::mem::swap(&mut self.camera, &mut self.panes[self.pane]);
+        self.pane = index;
+        std::mem::swap(&mut self.camera, &mut self.panes[index]);
+    }
+
+    fn frame_all(&mut self) { //centre the view on the displayed model
+        self.frame(&(0..self.vertices.items.len()).collect::<Vec<_>>());
+        self.status = "Framed model".to_string();
+    }
+
+    fn frame_selection(&mut self) { //centre the view on the selected vertex or face
+        let indices = match self.tab_index {
+            0 => self.vertices.state.selected().into_iter().collect(),
+            1 => self.faces.state.selected().map_or(Vec::new(), |i| self.faces.items[i].vertices.iter().map(|&v| v as usize).collect()),
+            _ => Vec::new(),
+        };
+        match indices.is_empty() {
+            true  => self.status = "Nothing selected to frame".to_string(),
+            false => {
+                self.frame(&indices);
+                self.status = "Framed selection".to_string();
+            }
+        }
+    }
+
+    fn frame(&mut self, indices: &[usize]) { //turn about the middle of the bounding box of some vertices and zoom to fit it
+        let positions: Vec<[f32; 3]> = indices.iter().map(|&i| self.vertices.items[i].position).collect();
+        let Some((min, max)) = bounding_box(&positions) else { return };
+        for camera in self.panes.iter_mut().chain([&mut self.camera]) {
+            camera.target = [0, 1, 2].map(|i| (min[i] + max[i]) / 2.0);
+            camera.pan = [0.0, 0.0];
+            camera.framing = (min != max).then_some((min, max));
+        }
+    }
+
+    fn toggle_perspective(&mut self) {
+        self.camera.set_perspective(!self.camera.perspective);
+        self.status = match self.camera.perspective {
+            true  => format!("Perspective view, field of view {:.0}°", self.camera.fov.to_degrees()),
+            false => "Orthographic view".to_string(),
+        };
+    }
+
+    fn widen_fov(&mut self, degrees: f64) {
+        let fov = self.camera.fov.to_degrees() + degrees;
+        self.camera.fov = fov.clamp(FOV_RANGE[0], FOV_RANGE[1]).to_radians();
+        self.status = format!("Field of view {:.0}°", self.camera.fov.to_degrees());
+    }
+}
+
+fn bounding_box(positions: &[[f32; 3]]) -> Option<([f64; 3], [f64; 3])> { //smallest and largest coordinates; none without positions
+    let first = positions.first()?.map(|c| c as f64);
+    Some(positions.iter().fold((first, first), |(min, max), p| {
+        ([0, 1, 2].map(|i| min[i].min(p[i] as f64)), [0, 1, 2].map(|i| max[i].max(p[i] as f64)))
+    }))
+}
+
+fn parse_transform(input: &str) -> Result<Transform, String> { //"t x y z", "r axis degrees" or "s factor"
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let numbers = |words: &[&str]| -> Result<Vec<f64>, String> {
+        words.iter().map(|w| w.parse().map_err(|_| format!("invalid number: {}", w))).collect()
+    };
+
+    match words.split_first() {
+        Some((&("t" | "translate"), args)) => match numbers(args)?[..] {
+            [x, y, z] => Ok(Transform::Translate([x, y, z])),
+            _ => Err("translate takes x y z".to_string()),
+        },
+        Some((&("r" | "rotate"), args)) => {
+            let (axis, angle) = match args {
+                [name, angle] => match *name {
+                    "x" => ([1.0, 0.0, 0.0], angle),
+                    "y" => ([0.0, 1.0, 0.0], angle),
+                    "z" => ([0.0, 0.0, 1.0], angle),
+                    _ => return Err(format!("unknown axis: {}", name)),
+                },
+                [x, y, z, angle] => match numbers(&[x, y, z])?[..] {
+                    [x, y, z] => ([x, y, z], angle),
+                    _ => unreachable!(),
+                },
+                _ => return Err("rotate takes an axis (x, y, z or a vector) and degrees".to_string()),
+            };
+            let length = (axis[0] * axis[0] + axis[1] * axis[1] + axis[2] * axis[2]).sqrt();
+            if length == 0.0 {
+                return Err("rotation axis cannot be zero".to_string());
+            }
+            let angle = numbers(&[angle])?[0];
+            Ok(Transform::Rotate(axis.map(|a| a / length), angle.to_radians()))
+        },
+        Some((&("s" | "scale"), args)) => match numbers(args)?[..] {
+            [k] => Ok(Transform::Scale([k; 3])),
+            [x, y, z] => Ok(Transform::Scale([x, y, z])),
+            _ => Err("scale takes one factor or x y z".to_string()),
+        },
+        _ => Err("unknown transform; use t x y z, r axis degrees or s factor".to_string()),
+    }
+}
+
+fn nearest_source<'a>(sources: impl Iterator<Item = Option<&'a Source>>, line: usize, after: bool) -> Option<(usize, usize)> { //line and index of the closest statement read after or before a line
+    let found = sources.enumerate().filter_map(|(i, source)| source.map(|source| (source.line, i)));
+    match after {
+        true  => found.filter(|&(l, _)| l > line).min(),
+        false => found.filter(|&(l, _)| l < line).max(),
+    }
+}
+
+fn parse_obj(text: &str, name: &str) -> Result<Vec<Model>, String> { //obj read; keeps enough layout to write the file back unchanged
+    match read_obj(text, name, true)? {
+        Some(models) => Ok(models),
+        //a face uses a vertex from another object, so the objects cannot be edited apart
+        None => Ok(read_obj(text, name, false)?.unwrap_or_default()),
+    }
+}
+
+fn read_obj(text: &str, name: &str, split_objects: bool) -> Result<Option<Vec<Model>>, String> {
+    let mut models = vec![Model::new(name)];
+    let mut owners: Vec<(usize, u32)> = Vec::new(); //model and local index of each v statement
+    let (mut texcoord_count, mut normal_count) = (0, 0);
+    let mut prefix = Vec::new();
+
+    for (number, line) in text.split_inclusive('\n').enumerate() {
+        let model = models.len() - 1;
+        let source = |prefix: &mut Vec<String>| Some(Source {
+            line: number,
+            text: line.to_string(),
+            prefix: std::mem::take(prefix),
+        });
+
+        match line.split_whitespace().next() {
+            Some("v") => {
+                let position = parse_vertex(line).map_err(|e| format!("line {}: {}", number + 1, e))?;
+                owners.push((model, models[model].vertices.len() as u32));
+                models[model].vertices.push(Vertex { position, source: source(&mut prefix) });
+            },
+            Some("f") => {
+                let mut face = parse_face(line, [owners.len(), texcoord_count, normal_count])
+                    .map_err(|e| format!("line {}: {}", number + 1, e))?;
+                for v in face.vertices.iter_mut() {
+                    let (owner, local) = owners[*v as usize];
+                    if owner != model {
+                        return Ok(None);
+                    }
+                    *v = local;
+                }
+                face.source = source(&mut prefix);
+                models[model].faces.push(face);
+            },
+            Some("o") if split_objects => {
+                let object_name = line.trim()[1..].trim();
+                let mut object = Model::new(if object_name.is_empty() { name } else { object_name });
+                object.header = source(&mut prefix);
+                models.push(object);
+            },
+            keyword => {
+                match keyword {
+                    Some("vt") => texcoord_count += 1,
+                    Some("vn") => normal_count += 1,
+                    _ => {}
+                }
+                prefix.push(line.to_string());
+            }
+        }
+    }
+
+    if let Some(model) = models.last_mut() {
+        model.trailer = prefix;
+    }
+    //drop the unnamed model in front of the first `o` when nothing was read into it
+    if models.len() > 1 && models[0].vertices.is_empty() && models[0].faces.is_empty() {
+        models.remove(0);
+    }
+
+    Ok(Some(models))
+}
+
+fn parse_vertex(text: &str) -> Result<[f32; 3], String> { //position of a `v` statement; extra values are ignored
+    let mut position = [0.0; 3];
+    let mut values = text.split_whitespace().skip(1);
+    for p in position.iter_mut() {
+        *p = match values.next().map(str::parse) {
+            Some(Ok(value)) => value,
+            _ => return Err(format!("invalid vertex `{}`", text.trim())),
+        };
+    }
+    Ok(position)
+}
+
+fn parse_face(text: &str, counts: [usize; 3]) -> Result<Face, String> { //corners of an `f` statement; counts are the v, vt and vn statements before it
+    let error = || format!("invalid face `{}`", text.trim());
+    let mut indices: [Vec<u32>; 3] = Default::default();
+    let mut corners = 0;
+
+    for corner in text.split_whitespace().skip(1) {
+        for (slot, part) in corner.split('/').enumerate() {
+            if slot > 2 || (slot == 0 && part.is_empty()) {
+                return Err(error());
+            }
+            if part.is_empty() {
+                continue;
+            }
+            //negative indices count back from the latest statement
+            let index: i64 = part.parse().map_err(|_| error())?;
+            let count = counts[slot] as i64;
+            let resolved = if index < 0 { count + index } else { index - 1 };
+            if index == 0 || resolved < 0 || resolved >= count {
+                return Err(format!("index {} out of range in `{}`", index, text.trim()));
+            }
+            indices[slot].push(resolved as u32);
+        }
+        corners += 1;
+    }
+
+    //every corner needs the same kind of indices
+    if corners < 3 || indices[1..].iter().any(|list| !list.is_empty() && list.len() != corners) {
+        return Err(error());
+    }
+
+    let [vertices, texcoords, normals] = indices;
+    Ok(Face { vertices, texcoords, normals, source: None })
+}
+
+fn parse_stl(data: &[u8], name: &str) -> Result<Model, String> { //stl read; identical corners are welded into shared vertices
+    //binary files are an 80 byte header, a triangle count and 50 bytes per triangle
+    let binary = match data.get(80..84) {
+        Some(count) => {
+            let size = 84 + 50 * u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize;
+            data.len() == size || (data.len() > size && !data.starts_with(b"solid"))
+        },
+        None => false,
+    };
+
+    let facets = match binary {
+        true  => read_binary_stl(data),
+        false => match std::str::from_utf8(data) {
+            Ok(text) if text.trim_start().starts_with("solid") => read_ascii_stl(text)?,
+            _ => return Err("not an ascii or binary stl file".to_string()),
+        },
+    };
+
+    let mut model = Model::new(name);
+    let mut welded: HashMap<[u32; 3], u32> = HashMap::new();
+    for facet in facets {
+        let mut face = Vec::new();
+        for position in facet {
+            //adding zero turns -0.0 into 0.0 so both weld together
+            let key = position.map(|c| (c + 0.0).to_bits());
+            let index = *welded.entry(key).or_insert_with(|| {
+                model.vertices.push(Vertex::new(position));
+                model.vertices.len() as u32 - 1
+            });
+            if !face.contains(&index) {
+                face.push(index);
+            }
+        }
+        //facets collapsed by welding have no area left
+        if face.len() >= 3 {
+            model.faces.push(Face::new(face));
+        }
+    }
+
+    Ok(model)
+}
+
+fn read_binary_stl(data: &[u8]) -> Vec<Vec<[f32; 3]>> {
+    let value = |at: usize| f32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]);
+    let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as usize;
+
+    //each triangle is a normal, three corners and a two byte attribute
+    (0..count).map(|t| {
+        let start = 84 + 50 * t + 12;
+        (0..3).map(|c| {
+            let at = start + 12 * c;
+            [value(at), value(at + 4), value(at + 8)]
+        }).collect()
+    }).collect()
+}
+
+fn read_ascii_stl(text: &str) -> Result<Vec<Vec<[f32; 3]>>, String> {
+    let mut facets = Vec::new();
+    let mut corners = Vec::new();
+    let mut words = text.split_whitespace();
+
+    while let Some(word) = words.next() {
+        match word {
+            "vertex" => {
+                let mut position = [0.0; 3];
+                for p in position.iter_mut() {
+                    *p = match words.next().map(str::parse) {
+                        Some(Ok(value)) => value,
+                        _ => return Err(format!("invalid vertex after {} facets", facets.len())),
+                    };
+                }
+                corners.push(position);
+            },
+            "endloop" => {
+                if corners.len() < 3 {
+                    return Err(format!("facet {} has fewer than 3 vertices", facets.len() + 1));
+                }
+                facets.push(std::mem::take(&mut corners));
+            },
+            _ => {}
+        }
+    }
+
+    if !corners.is_empty() {
+        return Err(format!("facet {} is cut off", facets.len() + 1));
+    }
+    Ok(facets)
+}
+
+fn write_stl(models: &[Model], binary: bool) -> Vec<u8> { //stl write; polygons are split into triangle fans
+    if binary {
+        let triangles: Vec<[[f32; 3]; 3]> = models.iter().flat_map(model_triangles).collect();
+        let mut stl = b"binary STL written by tui_obj".to_vec();
+        stl.resize(80, 0);
+        stl.extend((triangles.len() as u32).to_le_bytes());
+        for [a, b, c] in triangles {
+            for value in triangle_normal(a, b, c).iter().chain(&a).chain(&b).chain(&c) {
+                stl.extend(value.to_le_bytes());
+            }
+            stl.extend([0, 0]); //attribute byte count
+        }
+        stl
+    } else {
+        let mut stl = String::new();
+        for model in models {
+            stl.push_str(&format!("solid {}\n", model.name));
+            for [a, b, c] in model_triangles(model) {
+                let n = triangle_normal(a, b, c);
+                stl.push_str(&format!("  facet normal {} {} {}\n    outer loop\n", n[0], n[1], n[2]));
+                for v in [a, b, c] {
+                    stl.push_str(&format!("      vertex {} {} {}\n", v[0], v[1], v[2]));
+                }
+                stl.push_str("    endloop\n  endfacet\n");
+            }
+            stl.push_str(&format!("endsolid {}\n", model.name));
+        }
+        stl.into_bytes()
+    }
+}
+
+fn model_triangles(model: &Model) -> Vec<[[f32; 3]; 3]> { //corner positions of every face, fanned out from its first corner
+    let mut triangles = Vec::new();
+    for face in &model.faces {
+        let corner = |i: usize| model.vertices[face.vertices[i] as usize].position;
+        for i in 1..face.vertices.len() - 1 {
+            triangles.push([corner(0), corner(i), corner(i + 1)]);
+        }
+    }
+    triangles
+}
+
+fn triangle_normal(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> [f32; 3] { //unit normal by the right-hand rule; zero for degenerate triangles
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let n = [u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]];
+    let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    match length > 0.0 {
+        true  => n.map(|c| c / length),
+        false => [0.0; 3],
+    }
+}
+
+fn write_obj(models: &[Model]) -> String { //obj write; statements still matching their source text are copied verbatim
+    //match the line ending of the source file
+    let eol = match models.iter().flat_map(|m| &m.vertices).find_map(|v| v.source.as_ref()) {
+        Some(source) if source.text.ends_with("\r\n") => "\r\n",
+        _ => "\n",
+    };
+
+    let mut obj = String::new();
+    let mut counts = [0; 3]; //v, vt and vn statements written so far
+    for (index, model) in models.iter().enumerate() {
+        let offset = counts[0] as u32;
+
+        match &model.header {
+            Some(header) => {
+                push_lines(&mut obj, &header.prefix, &mut counts, eol);
+                //a bare `o` gave the model the file's name, which stays unwritten
+                let header_name = header.text.trim()[1..].trim();
+                if header_name == model.name || header_name.is_empty() {
+                    push_line(&mut obj, &header.text, eol);
+                } else {
+                    push_line(&mut obj, &format!("o {}{}", model.name, eol), eol);
+                }
+            },
+            None if index > 0 => push_line(&mut obj, &format!("o {}{}", model.name, eol), eol),
+            None => {}
+        }
+
+        //interleave vertices and faces in file order; new statements follow the last one read
+        let (mut v, mut f) = (0, 0);
+        let (mut v_line, mut f_line) = (0, 0);
+        loop {
+            let vertex = model.vertices.get(v);
+            let face = model.faces.get(f);
+            if let Some(source) = vertex.and_then(|vertex| vertex.source.as_ref()) {
+                v_line = source.line;
+            }
+            if let Some(source) = face.and_then(|face| face.source.as_ref()) {
+                f_line = source.line;
+            }
+
+            match (vertex, face) {
+                (Some(vertex), face) if face.is_none() || v_line <= f_line => {
+                    let unchanged = match &vertex.source {
+                        Some(source) => {
+                            push_lines(&mut obj, &source.prefix, &mut counts, eol);
+                            parse_vertex(&source.text) == Ok(vertex.position)
+                        },
+                        None => false,
+                    };
+                    match (unchanged, &vertex.source) {
+                        (true, Some(source)) => push_line(&mut obj, &source.text, eol),
+                        (false, source) => {
+                            //values after the position, such as w or a vertex colour, are kept
+                            let extra: String = source.iter().flat_map(|source| source.text.split_whitespace().skip(4)).map(|value| format!(" {}", value)).collect();
+                            let [x, y, z] = vertex.position;
+                            push_line(&mut obj, &format!("v {} {} {}{}{}", x, y, z, extra, eol), eol);
+                        },
+                        (true, None) => unreachable!(),
+                    }
+                    counts[0] += 1;
+                    v += 1;
+                },
+                (_, Some(face)) => {
+                    let vertices: Vec<u32> = face.vertices.iter().map(|i| i + offset).collect();
+                    let unchanged = match &face.source {
+                        Some(source) => {
+                            push_lines(&mut obj, &source.prefix, &mut counts, eol);
+                            match parse_face(&source.text, counts) {
+                                Ok(parsed) => {
+                                    parsed.vertices == vertices &&
+                                    parsed.texcoords == face.texcoords &&
+                                    parsed.normals == face.normals
+                                },
+                                Err(_) => false,
+                            }
+                        },
+                        None => false,
+                    };
+                    match (unchanged, &face.source) {
+                        (true, Some(source)) => push_line(&mut obj, &source.text, eol),
+                        _ => push_line(&mut obj, &format_face(&vertices, face, eol), eol),
+                    }
+                    f += 1;
+                },
+                _ => break,
+            }
+        }
+
+        push_lines(&mut obj, &model.trailer, &mut counts, eol);
+    }
+
+    obj
+}
+
+fn format_face(vertices: &[u32], face: &Face, eol: &str) -> String { //`f` statement from global 0-based vertex indices
+    let mut text = "f".to_string();
+    for (corner, v) in vertices.iter().enumerate() {
+        text.push_str(&match (face.texcoords.get(corner), face.normals.get(corner)) {
+            (Some(vt), Some(vn)) => format!(" {}/{}/{}", v + 1, vt + 1, vn + 1),
+            (Some(vt), None)     => format!(" {}/{}", v + 1, vt + 1),
+            (None, Some(vn))     => format!(" {}//{}", v + 1, vn + 1),
+            (None, None)         => format!(" {}", v + 1),
+        });
+    }
+    text.push_str(eol);
+    text
+}
+
+fn push_lines(obj: &mut String, lines: &[String], counts: &mut [usize; 3], eol: &str) { //append unparsed lines, counting the texcoords and normals among them
+    for line in lines {
+        match line.split_whitespace().next() {
+            Some("vt") => counts[1] += 1,
+            Some("vn") => counts[2] += 1,
+            _ => {}
+        }
+        push_line(obj, line, eol);
+    }
+}
+
+fn push_line(obj: &mut String, line: &str, eol: &str) { //append a line, ending an unterminated last line first
+    if !obj.is_empty() && !obj.ends_with('\n') {
+        obj.push_str(eol);
+    }
+    obj.push_str(line);
+}
+
+const USAGE: &str = "\
+usage: tui_obj [FILE] [OPTIONS]
+       tui_obj render [FILE] [OPTIONS] [--width W] [--height H] [--ascii]
+
+  FILE                  .obj or .stl file to open at startup
+  -t, --tab TAB         starting tab: vertex, face or help (default: vertex with FILE, else help)
+  -z, --zoom ZOOM       half-width of the viewport in model units (default: fit the model)
+      --zoom-step F     zoom factor of one +/- key press (default 1.1)
+  -r, --yaw DEG         starting turn of the viewport about the vertical axis, in degrees
+  -p, --pitch DEG       starting tilt of the viewport in degrees; 90 looks straight down
+      --roll DEG        starting roll of the viewport in degrees
+  -V, --view VIEW       starting view: front, back, left, right, top, bottom or iso
+  -s, --solid           fill and shade faces instead of drawing points or edges
+  -e, --hidden-lines    draw only the outline and creases of the wireframe
+      --crease DEG      angle between faces for their edge to show with -e (default 30)
+  -P, --perspective     start in perspective instead of orthographic projection
+      --fov DEG         perspective field of view in degrees (default 60)
+      --distance D      perspective distance from the eye to the centre of the view
+      --near N          perspective depth below which geometry is clipped (default 0.1)
+  -h, --help            print this message
+
+render prints the viewport to stdout without a terminal; the vertex tab
+draws points and the face tab (the default) draws the wireframe
+  -W, --width W         columns of the render (default 80)
+  -H, --height H        rows of the render (default 24)
+      --ascii           draw with ascii characters instead of braille";
+
+#[derive(Default)]
+struct Options { //command-line options
+    path: Option<String>,
+    tab: Option<usize>,
+    zoom: Option<f64>,
+    zoom_step: Option<f64>,
+    yaw: Option<f64>,
+    pitch: Option<f64>,
+    roll: Option<f64>,
+    solid: bool,
+    hidden_lines: bool,
+    crease: Option<f64>,
+    perspective: bool,
+    fov: Option<f64>,
+    distance: Option<f64>,
+    near: Option<f64>,
+    width: Option<u16>, //render only
+    height: Option<u16>,
+    ascii: bool,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+
+    let mut words = args.iter();
+    while let Some(word) = words.next() {
+        let mut value = || words.next().ok_or(format!("{} needs a value", word));
+        let number = |value: &str| value.parse::<f64>().map_err(|_| format!("invalid number for {}: {}", word, value));
+        let positive = |value: &str| match number(value)? {
+            number if number > 0.0 => Ok(number),
+            _ => Err(format!("{} must be positive", word)),
+        };
+        let size = |value: &str| match value.parse::<u16>() {
+            Ok(size) if size > 0 => Ok(size),
+            _ => Err(format!("invalid size for {}: {}", word, value)),
+        };
+        match word.as_str() {
+            "-t" | "--tab" => options.tab = Some(match value()?.as_str() {
+                "vertex" | "v" => 0,
+                "face" | "f" => 1,
+                "help" | "h" => 2,
+                other => return Err(format!("unknown tab: {}", other)),
+            }),
+            "-z" | "--zoom" => options.zoom = Some(positive(value()?)?),
+            "--zoom-step" => match number(value()?)? {
+                step if step > 1.0 => options.zoom_step = Some(step),
+                _ => return Err("zoom step must be greater than 1".to_string()),
+            },
+            "-r" | "--yaw" | "--rotation" => options.yaw = Some(number(value()?)?.to_radians()),
+            "-p" | "--pitch" => options.pitch = Some(number(value()?)?.to_radians()),
+            "--roll" => options.roll = Some(number(value()?)?.to_radians()),
+            "-V" | "--view" => {
+                let name = value()?;
+                match find_view(name) {
+                    Some(&(_, _, yaw, pitch)) => (options.yaw, options.pitch) = (Some(yaw.to_radians()), Some(pitch.to_radians())),
+                    None => return Err(format!("unknown view: {}", name)),
+                }
+            },
+            "-s" | "--solid" => options.solid = true,
+            "-e" | "--hidden-lines" => options.hidden_lines = true,
+            "--crease" => match number(value()?)? {
+                angle if (0.0..=180.0).contains(&angle) => options.crease = Some(angle.to_radians()),
+                _ => return Err("crease must be between 0 and 180 degrees".to_string()),
+            },
+            "-P" | "--perspective" => options.perspective = true,
+            "--fov" => match number(value()?)? {
+                fov if (FOV_RANGE[0]..=FOV_RANGE[1]).contains(&fov) => options.fov = Some(fov.to_radians()),
+                _ => return Err(format!("fov must be between {} and {} degrees", FOV_RANGE[0], FOV_RANGE[1])),
+            },
+            "--distance" => options.distance = Some(positive(value()?)?),
+            "--near" => options.near = Some(positive(value()?)?),
+            "-W" | "--width" => options.width = Some(size(value()?)?),
+            "-H" | "--height" => options.height = Some(size(value()?)?),
+            "--ascii" => options.ascii = true,
+            other if other.starts_with('-') => return Err(format!("unknown option: {}", other)),
+            other if options.path.is_none() => options.path = Some(other.to_string()),
+            other => return Err(format!("unexpected argument: {}", other)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn apply_options(app: &mut App, options: &Options) -> Result<(), String> {
+    //open first so the view options apply to the loaded model; a file starts in the vertex tab
+    if let Some(path) = &options.path {
+        if !app.open_file(path) {
+            return Err(app.status.to_string());
+        }
+    }
+    match (options.tab, &options.path) {
+        (Some(tab), _) => app.tab_index = tab,
+        (None, Some(_)) => app.tab_index = 0,
+        (None, None) => {}
+    }
+    if let Some(zoom) = options.zoom {
+        app.camera.zoom = zoom;
+        app.camera.framing = None;
+    }
+    if let Some(yaw) = options.yaw {
+        app.camera.yaw = yaw;
+    }
+    if let Some(pitch) = options.pitch {
+        app.camera.pitch = pitch;
+    }
+    if let Some(roll) = options.roll {
+        app.camera.roll = roll;
+    }
+    if let Some(fov) = options.fov {
+        app.camera.fov = fov;
+    }
+    if let Some(near) = options.near {
+        app.camera.near = near;
+    }
+    if let Some(zoom_step) = options.zoom_step {
+        app.camera.zoom_step = zoom_step;
+    }
+    app.camera.set_perspective(options.perspective);
+    app.solid = options.solid;
+    app.hidden_lines = options.hidden_lines;
+    if let Some(crease) = options.crease {
+        app.crease_angle = crease;
+    }
+    if let Some(distance) = options.distance {
+        app.camera.distance = distance;
+        app.camera.framing = None;
+    }
+    Ok(())
+}
+
+fn render_text(args: &[String]) -> Result<String, String> { //headless viewport render for scripts and ci
+    let mut options = parse_options(args)?;
+    options.tab = options.tab.or(Some(1));
+    let mut app = App::new();
+    apply_options(&mut app, &options)?;
+    if options.ascii {
+        app.marker = Marker::Dot;
+    }
+
+    //draw one cell larger all round and leave the viewport border out
+    let (width, height) = (options.width.unwrap_or(80), options.height.unwrap_or(24));
+    let mut terminal = Terminal::new(TestBackend::new(width + 2, height + 2)).map_err(|e| e.to_string())?;
+    terminal.draw(|f| {
+        let area = f.size();
+        draw_viewport(f, &mut app, area);
+    }).map_err(|e| e.to_string())?;
+
+    let buffer = terminal.backend().buffer();
+    let mut text = String::new();
+    for y in 1..=height {
+        let mut line = String::new();
+        for x in 1..=width {
+            let symbol = &buffer.get(x, y).symbol;
+            line.push_str(if options.ascii && symbol == "•" { "*" } else { symbol });
+        }
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+fn main() -> io::Result<()> {
+    //read command-line options before taking over the terminal
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    if args.first().is_some_and(|a| a == "render") {
+        match render_text(&args[1..]) {
+            Ok(text) => print!("{}", text),
+            Err(e) => {
+                eprintln!("tui_obj: {}", e);
+                std::process::exit(2);
+            }
+        }
+        return Ok(());
+    }
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("tui_obj: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let mut app = App::new();
+    if let Err(e) = apply_options(&mut app, &options) {
+        eprintln!("tui_obj: {}", e);
+        std::process::exit(1);
+    }
+
+    //put the terminal back before a panic message is printed
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, cursor::Show);
+        default_hook(info);
+    }));
+
+    //init terminal
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    //run terminal app
+    let exit_res = run(&mut terminal, app);    
+
+    //reset terminal
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+    terminal.show_cursor()?;
+
+    exit_res
+}
+
+fn run<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
+    loop {
         
         //draw TUI
         terminal.draw(|f| ui(f, &mut app))?;        
 
+        if app.quit {
+            return Ok(());
+        }
+
         //read input event
-        if let Event::Key(key) = event::read()? {
+        let event = event::read()?;
+        if let (Event::Mouse(mouse), StatusMode::Normal) = (&event, &app.status_mode) {
+            app.mouse(*mouse);
+        }
+        if let Event::Key(key) = event {
       	    match app.status_mode {
                 StatusMode::Normal => if key.kind == KeyEventKind::Press {
                     match key.code {
@@ -307,8 +2449,14 @@ fn run<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
                             app.status = "".to_string();
                             app.status_mode = StatusMode::Open;
                         },
-                        KeyCode::Char('w') => app.write_file(),
+                        KeyCode::Char('w') => {
+                            //a selection is written to a new path rather than over the open file
+                            app.status = if app.marked_count() > 0 { "".to_string() } else { app.path.to_string() };
+                            app.status_mode = StatusMode::Write;
+                        },
+                        KeyCode::Char(':') => app.command_line(),
                         KeyCode::Char('u') => app.restore(),
+                        KeyCode::Char('r') => app.redo(),
                         KeyCode::Right => app.next_model(),
                         KeyCode::Left => app.prev_model(),
                         //tabs
@@ -319,50 +2467,112 @@ fn run<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
                     }
                     if app.tab_index < 2 {
                         match key.code {
+                            //named views
+                            KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::ALT) => {
+                                if let Some(view) = VIEWS.iter().find(|view| view.1 == c) {
+                                    app.set_view(view.0);
+                                }
+                            },
                             //list controls
                             KeyCode::Down => app.next_item(),
                             KeyCode::Up => app.prev_item(),
+                            KeyCode::Esc => app.unselect_item(),
+                            KeyCode::Char(' ') => app.mark_item(),
                             KeyCode::Char('n') => app.new_item(),
-                            KeyCode::Char('d') => app.delete_item(),
+                            KeyCode::Char('d') => app.execute("delete"),
+                            KeyCode::Char('p') => app.pick_item(),
                             KeyCode::Char('t') => app.translate(),
                             //viewport controls
-                            KeyCode::Char('-') => app.zoom_out(1.1),
-                            KeyCode::Char('+') => app.zoom_in(1.1),
-                            KeyCode::Char('7') => app.rotation_offset += -0.02,
-                            KeyCode::Char('9') => app.rotation_offset +=  0.02,
-                            KeyCode::Char('8') => app.y_offset +=  0.05 * app.zoom,
-                            KeyCode::Char('2') => app.y_offset += -0.05 * app.zoom,
-                            KeyCode::Char('6') => app.x_offset +=  0.05 * app.zoom,
-                            KeyCode::Char('4') => app.x_offset += -0.05 * app.zoom,
-                            KeyCode::Char('5') => app.top_down = !app.top_down,
+                            KeyCode::Char('-') => app.camera.zoom_out(),
+                            KeyCode::Char('+') => app.camera.zoom_in(),
+                            KeyCode::Char('7') => app.camera.yaw   += -ROTATE_STEP,
+                            KeyCode::Char('9') => app.camera.yaw   +=  ROTATE_STEP,
+                            KeyCode::Char('1') => app.camera.pitch += -ROTATE_STEP,
+                            KeyCode::Char('3') => app.camera.pitch +=  ROTATE_STEP,
+                            KeyCode::Char('/') => app.camera.roll  += -ROTATE_STEP,
+                            KeyCode::Char('*') => app.camera.roll  +=  ROTATE_STEP,
+                            KeyCode::Char('8') => app.camera.move_by( 0.0,  PAN_STEP),
+                            KeyCode::Char('2') => app.camera.move_by( 0.0, -PAN_STEP),
+                            KeyCode::Char('6') => app.camera.move_by( PAN_STEP,  0.0),
+                            KeyCode::Char('4') => app.camera.move_by(-PAN_STEP,  0.0),
+                            KeyCode::Char('5') => app.toggle_top_view(),
+                            KeyCode::Char('0') => app.toggle_perspective(),
+                            KeyCode::Home => app.frame_all(),
+                            KeyCode::Char('l') => app.toggle_quad_view(),
+                            KeyCode::Char('s') => app.toggle_solid(),
+                            KeyCode::Char('e') => app.toggle_hidden_lines(),
+                            KeyCode::Char('{') => app.widen_crease_angle(-5.0),
+                            KeyCode::Char('}') => app.widen_crease_angle(5.0),
+                            KeyCode::Char('c') => app.next_pane(),
+                            KeyCode::Char('.') => app.frame_selection(),
+                            KeyCode::Char('[') => app.widen_fov(-5.0),
+                            KeyCode::Char(']') => app.widen_fov(5.0),
                             _ => {}
                         }
                     }
                 },
+                //yes/no confirmation before replacing a file
+                StatusMode::Overwrite => if key.kind == KeyEventKind::Press {
+                    app.status_mode = StatusMode::Normal;
+                    match key.code {
+                        KeyCode::Char('y') => app.save_file(&app.input.to_string()),
+                        _ => app.status = "Operation cancelled".to_string(),
+                    }
+                },
+                StatusMode::Browse => if key.kind == KeyEventKind::Press {
+                    match key.code {
+                        KeyCode::Down => app.browser.next(),
+                        KeyCode::Up => app.browser.previous(),
+                        KeyCode::Enter | KeyCode::Right => app.browse_selected(),
+                        KeyCode::Backspace | KeyCode::Left => app.browse_up(),
+                        KeyCode::Esc => {
+                            app.status_mode = StatusMode::Normal;
+                            app.status = "Operation cancelled".to_string();
+                        },
+                        _ => {}
+                    }
+                },
+                StatusMode::StlFormat => if key.kind == KeyEventKind::Press {
+                    app.status_mode = StatusMode::Normal;
+                    match key.code {
+                        KeyCode::Char('a') => app.write_stl_file(&app.input.to_string(), false),
+                        KeyCode::Char('b') => app.write_stl_file(&app.input.to_string(), true),
+                        _ => app.status = "Operation cancelled".to_string(),
+                    }
+                },
                 //generic command input mode
                 _ => if key.kind == KeyEventKind::Press { 
                     match key.code {
-                        KeyCode::Enter => {
-                            match app.status_mode {
-                                //specific command function handled here
-                                StatusMode::Open => {
-                                    app.status_mode = StatusMode::Normal;
-                                    app.open_file(&app.status.to_string());
-                                },
-                                _ => unreachable!()
-                            }
-                        }
-                        KeyCode::Char(c) => {
-                            app.status.push(c);
-                        }
-                        KeyCode::Backspace => {
-                            app.status.pop();
-                        }
+                        //each prompt hands its text to a registry command
+                        KeyCode::Enter => match (&app.status_mode, app.status_mode.command()) {
+                            (StatusMode::Command, _) => app.enter_command(),
+                            (_, Some(command)) => {
+                                app.status_mode = StatusMode::Normal;
+                                app.execute(&format!("{} {}", command, app.status));
+                            },
+                            _ => {}
+                        },
+                        KeyCode::Up   if matches!(app.status_mode, StatusMode::Command) => app.browse_commands(true),
+                        KeyCode::Down if matches!(app.status_mode, StatusMode::Command) => app.browse_commands(false),
+                        KeyCode::Down if matches!(app.status_mode, StatusMode::Open) => {
+                            //browse from the directory typed so far
+                            let typed = app.status.to_string();
+                            let directory = match typed.rfind(['/', MAIN_SEPARATOR]) {
+                                Some(i) => &typed[..=i],
+                                None => ".",
+                            };
+                            app.open_browser(directory);
+                        },
+                        KeyCode::Tab if matches!(app.status_mode, StatusMode::Command | StatusMode::Open | StatusMode::Write) => app.complete(),
                         KeyCode::Esc => {
                             app.status_mode = StatusMode::Normal;
                             app.status = "Operation cancelled".to_string();
                         }
-                        _ => {}
+                        code => app.edit_prompt(code),
+                    }
+                    //a prompt opened next starts typing at the end
+                    if !matches!(app.status_mode, StatusMode::Command) && app.status_mode.command().is_none() {
+                        app.cursor = 0;
                     }
                 }
             }
@@ -392,6 +2602,10 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) { //TUI handler
     draw_header(f, app, chunks[0]);
     draw_tab_menu(f, app, chunks[1]);
 
+    //mouse targets are only where this draw puts them
+    app.list_area = Rect::default();
+    app.pane_areas.clear();
+
     //render selected tab
     match app.tab_index {
         0 => draw_vertex_tab(f, app, chunks[2]),
@@ -402,12 +2616,58 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) { //TUI handler
 
     draw_status(f, app, chunks[3]);
     draw_footer(f, app, chunks[4]);
+
+    if let StatusMode::Browse = app.status_mode {
+        draw_browser(f, app, chunks[2]);
+    }
+}
+
+fn draw_browser<B>(f: &mut Frame<B>, app: &mut App, area: Rect) //file browser popup over the middle of an area
+where
+    B: Backend,
+{
+    let popup = Rect::new(area.x + area.width / 6, area.y + area.height / 8, area.width - area.width / 3, area.height - area.height / 4);
+
+    //directories end with a slash, files show their size
+    let width = popup.width.saturating_sub(5) as usize;
+    let entries: Vec<ListItem> = app.browser.items.iter().map(|entry| {
+        let line = match entry.directory {
+            true  => format!("{}/", entry.name),
+            false => {
+                let size = format_size(entry.size);
+                format!("{:<1$}{2}", entry.name, width.saturating_sub(size.len() + 1).max(entry.name.len() + 1), size)
+            },
+        };
+        let style = match entry.directory {
+            true  => Style::default().fg(Color::LightCyan),
+            false => Style::default().fg(Color::White),
+        };
+        ListItem::new(line).style(style)
+    }).collect();
+
+    let list = List::new(entries)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::Yellow))
+                .title(app.browser_dir.to_string_lossy().to_string())
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+
+    f.render_widget(Clear, popup);
+    f.render_stateful_widget(list, popup, &mut app.browser.state);
 }
 
 fn draw_header<B>(f: &mut Frame<B>, app: &mut App, area: Rect) //header bit
 where
     B: Backend,
 {
+    //name and position of the displayed model
+    let active = app.active_model();
+    let title = format!("tui_OBJ - {} ({}/{})", app.models.items[active].name, active + 1, app.models.items.len());
+
     let header = Paragraph::new("tui_OBJ 2023 - copyright Simon Eagar - all rights reserved")
         .style(Style::default().fg(Color::LightCyan))
         .alignment(Alignment::Center)
@@ -415,7 +2675,7 @@ where
             Block::default()
                 .borders(Borders::ALL)
                 .style(Style::default().fg(Color::White))
-                .title("tui_OBJ")
+                .title(title)
                 .border_type(BorderType::Plain),
         );
     
@@ -457,12 +2717,10 @@ fn draw_status<B>(f: &mut Frame<B>, app: &mut App, area: Rect) //status message
 where
     B: Backend,
 {
-    let status_bar;
-    
-    //match formatting to app state
-    match app.status_mode {
+    //match formatting to app state; prompts differ only in title and colour
+    let status_bar = match app.status_mode {
         StatusMode::Normal => {
-            status_bar = Paragraph::new(&*app.status)
+            Paragraph::new(&*app.status)
                 .style(Style::default().fg(Color::LightCyan))
                 .alignment(Alignment::Center)
                 .block(
@@ -471,38 +2729,56 @@ where
                         .style(Style::default().fg(Color::White))
                         .title("Status Update")
                         .border_type(BorderType::Plain),
-                );
+                )
         },
-        StatusMode::Open => {
-            status_bar = Paragraph::new(&*app.status)
-                .style(Style::default().fg(Color::White))
-                .alignment(Alignment::Left)
-                .block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .style(Style::default().fg(Color::Yellow))
-                        .title("Open File")
-                        .border_type(BorderType::Plain),
-                );
+        StatusMode::Open => prompt_bar(&*app.status, "Open File - Tab completes, Down browses", Color::Yellow),
+        StatusMode::Write => prompt_bar(&*app.status, if app.marked_count() > 0 { "Write Selection" } else { "Write File" }, Color::Yellow),
+        StatusMode::NewVertex => prompt_bar(&*app.status, "New Vertex (x y z)", Color::Yellow),
+        StatusMode::NewFace => prompt_bar(&*app.status, "New Face (vertex numbers)", Color::Yellow),
+        StatusMode::Transform => prompt_bar(&*app.status, "Transform (t x y z | r axis degrees | s factor)", Color::Yellow),
+        StatusMode::Command => {
+            //name the commands or arguments the word being typed could complete to
+            let found = completions(&app.status).1;
+            let title = match found.is_empty() {
+                true  => "Command".to_string(),
+                false => format!("Command - {}", found.join(" | ")),
+            };
+            prompt_bar(format!(":{}", app.status), title, Color::Yellow)
         },
-        _ => {
-            status_bar = Paragraph::new(&*app.status)
-                .style(Style::default().fg(Color::White))
-                .alignment(Alignment::Left)
-                .block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .style(Style::default().fg(Color::Yellow))
-                        .title("")
-                        .border_type(BorderType::Plain),
-                );
-        }
-    }
+        StatusMode::Browse => prompt_bar("Enter opens, Backspace goes up, Esc cancels", "Browse Files", Color::Yellow),
+        StatusMode::Overwrite => prompt_bar(&*app.status, "Confirm Overwrite", Color::LightRed),
+        StatusMode::StlFormat => prompt_bar(&*app.status, "STL Format", Color::Yellow),
+    };
     
     f.render_widget(status_bar, area);
+
+    //put the terminal cursor where typing goes
+    let prompt = match app.status_mode {
+        StatusMode::Command => Some(1),
+        _ => app.status_mode.command().map(|_| 0),
+    };
+    if let Some(prompt) = prompt {
+        let column = (prompt + app.status.chars().count() - app.cursor) as u16;
+        if column + 2 < area.width {
+            f.set_cursor(area.x + 1 + column, area.y + 1);
+        }
+    }
+}
+
+fn prompt_bar<'t>(text: impl Into<Text<'t>>, title: impl Into<Spans<'t>>, color: Color) -> Paragraph<'t> { //status bar of a prompt: typed text in white, framed and titled in a colour
+    Paragraph::new(text)
+        .style(Style::default().fg(Color::White))
+        .alignment(Alignment::Left)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(color))
+                .title(title)
+                .border_type(BorderType::Plain),
+        )
 }
 
-fn draw_footer<B>(f: &mut Frame<B>, app: &mut App, area: Rect) //footer bit
+fn draw_footer<B>(f: &mut Frame<B>, _app: &mut App, area: Rect) //footer bit
 where
     B: Backend,
 {
@@ -563,14 +2839,17 @@ where
     let mut vertices: Vec<ListItem> = vec![];
     
     //build formatted list from vertex data
-    for i in 0..app.vertices.items.len() / 3 {
-        let mut lines = Spans::from(vec![
+    for (i, vertex) in app.vertices.items.iter().enumerate() {
+        let lines = Spans::from(vec![
             Span::raw(format!("v{}:", i + 1)),
-            Span::raw(format!("    {}", app.vertices.items[3 * i])),
-            Span::raw(format!("    {}", app.vertices.items[3 * i + 1])),
-            Span::raw(format!("    {}", app.vertices.items[3 * i + 2])),
+            Span::raw(format!("    {}", vertex.position[0])),
+            Span::raw(format!("    {}", vertex.position[1])),
+            Span::raw(format!("    {}", vertex.position[2])),
         ]);
         let mut lines_item: ListItem = ListItem::new(lines);
+        if app.vertices.marked.contains(&i) {
+            lines_item = lines_item.style(Style::default().fg(Color::Yellow));
+        }
         vertices.push(lines_item);
     }
 
@@ -584,6 +2863,8 @@ where
         .highlight_symbol(">> ");
     
     f.render_stateful_widget(list_vertex, area, &mut app.vertices.state);
+    app.vertices.scroll(area.height.saturating_sub(2) as usize);
+    app.list_area = area;
 }
 
 fn draw_face_list<B>(f: &mut Frame<B>, app: &mut App, area: Rect) //build face list
@@ -593,14 +2874,16 @@ where
     let mut faces: Vec<ListItem> = vec![];
     
     //build formatted list from vertex data
-    for i in 0..app.faces.items.len() / 3 {
-        let mut lines = Spans::from(vec![
-            Span::raw(format!("f{}:", i + 1)),
-            Span::raw(format!("    {}", app.faces.items[3 * i])),
-            Span::raw(format!("    {}", app.faces.items[3 * i + 1])),
-            Span::raw(format!("    {}", app.faces.items[3 * i + 2])),
-        ]);
+    for (i, face) in app.faces.items.iter().enumerate() {
+        let mut spans = vec![Span::raw(format!("f{}:", i + 1))];
+        for index in &face.vertices {
+            spans.push(Span::raw(format!("    {}", index + 1)));
+        }
+        let lines = Spans::from(spans);
         let mut lines_item: ListItem = ListItem::new(lines);
+        if app.faces.marked.contains(&i) {
+            lines_item = lines_item.style(Style::default().fg(Color::Yellow));
+        }
         faces.push(lines_item);
     }
 
@@ -614,175 +2897,513 @@ where
         .highlight_symbol(">> ");
     
     f.render_stateful_widget(list_face, area, &mut app.faces.state);
+    app.faces.scroll(area.height.saturating_sub(2) as usize);
+    app.list_area = area;
+}
+
+fn draw_viewport<B>(f: &mut Frame<B>, app: &mut App, area: Rect) //draw viewport, or the four panes of the quad view
+where
+    B: Backend,
+{
+    if !app.quad_view {
+        let mut camera = app.camera;
+        draw_pane(f, app, &mut camera, false, area);
+        app.camera = camera;
+        app.pane_areas.push((app.pane, area));
+        return draw_selection_box(f, app);
+    }
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(area);
+    for (row, &row_area) in rows.iter().enumerate() {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(row_area);
+        for (column, &pane_area) in columns.iter().enumerate() {
+            let pane = row * 2 + column;
+            let mut camera = match pane == app.pane {
+                true  => app.camera,
+                false => app.panes[pane],
+            };
+            draw_pane(f, app, &mut camera, pane == app.pane, pane_area);
+            app.pane_areas.push((pane, pane_area));
+            if pane == app.pane {
+                draw_selection_box(f, app);
+            }
+            match pane == app.pane {
+                true  => app.camera = camera,
+                false => app.panes[pane] = camera,
+            }
+        }
+    }
+}
+
+fn draw_selection_box<B>(f: &mut Frame<B>, app: &mut App) //outline of a box selection being dragged
+where
+    B: Backend,
+{
+    let area = app.pane_areas.iter().find(|&&(index, _)| index == app.pane).map(|&(_, area)| area);
+    if let (Some(c), Some(area)) = (app.selection_box, area) {
+        let (left, top) = (c[0].min(c[2]).max(area.x), c[1].min(c[3]).max(area.y));
+        let (right, bottom) = (c[0].max(c[2]).min(area.right() - 1), c[1].max(c[3]).min(area.bottom() - 1));
+        if left < right && top < bottom {
+            let outline = Block::default().borders(Borders::ALL).border_type(BorderType::Plain).border_style(Style::default().fg(Color::Yellow));
+            f.render_widget(outline, Rect::new(left, top, right - left + 1, bottom - top + 1));
+        }
+    }
 }
 
-fn draw_viewport<B>(f: &mut Frame<B>, app: &mut App, area: Rect) //draw viewport; just redirect to necessary function
+fn draw_pane<B>(f: &mut Frame<B>, app: &mut App, camera: &mut Camera, focused: bool, area: Rect) //render one camera's view; just redirect to necessary function
 where
     B: Backend,
 {
-    if app.tab_index == 0 {
-        dot_render(f, app, area);
+    if let Some(bounds) = camera.framing.take() {
+        camera.fit(bounds, area);
+    }
+    let mut block = Block::default().title(viewport_title(camera)).borders(Borders::ALL);
+    if focused {
+        block = block.border_style(Style::default().fg(Color::LightCyan));
+    }
+    if app.solid {
+        solid_render(f, app, camera, block, area);
+    } else if app.tab_index == 0 {
+        dot_render(f, app, camera, block, area);
     } else {
-        line_render(f, app, area);
+        line_render(f, app, camera, block, area);
     };
 }
 
-fn dot_render<B>(f: &mut Frame<B>, app: &mut App, area: Rect) //render dot model
+fn dot_render<B>(f: &mut Frame<B>, app: &mut App, camera: &Camera, block: Block, area: Rect) //render dot model
 where
     B: Backend,
 {
-    let x_zoom = app.zoom;
-    let y_zoom = app.zoom * 2.0 * area.height as f64 / area.width as f64;
-    let (y_component, z_component) = match app.top_down { //determine whether model will rotate about y-axis or z-axis
-        false => (1, 2),
-        true  => (2, 1),
-    };
+    let (x_bounds, y_bounds) = camera.bounds(area);
     
     let mut viewport = Canvas::default()
-    	.block(Block::default().title("Viewport").borders(Borders::ALL))
-    	.x_bounds([x_zoom * -1.0, x_zoom])
-    	.y_bounds([y_zoom * -1.0, y_zoom]);
-    
-    let positions = &app.vertices.items;
-    let mut points: Vec<(f64, f64)> = Vec::new();
+    	.marker(app.marker)
+    	.block(block)
+    	.x_bounds(x_bounds)
+    	.y_bounds(y_bounds);
     
     //convert raw position data into renderable points
-    for index in 0..positions.len() / 3 {
-        let i = index * 3;
-        //basic sine/cosine rotation transform about y-axis
-        let x = {
-            positions[i] as f64 * 
-            app.rotation_offset.sin() + 
-            positions[i + z_component] as f64 * 
-            app.rotation_offset.cos()
-        };
-        points.push((x, positions[i + y_component] as f64));
-    }
+    let positions = view_positions(camera, &app.vertices.items);
+    let points: Vec<Option<(f64, f64)>> = positions.iter().map(|&v| camera.project(v)).collect();
     
     //draw points
     viewport = viewport.paint(|ctx| {
+        let visible: Vec<(f64, f64)> = points.iter().flatten().copied().collect();
         ctx.draw(&Points {
-            coords: &points,
+            coords: &visible,
             color: Color::White,
         });
-        //highlight selected point
-        match app.vertices.state.selected() {
-            Some(value) => {
-                let i = value * 3;
-                let x = {
-                    positions[i] as f64 * 
-                    app.rotation_offset.sin() + 
-                    positions[i + z_component] as f64 * 
-                    app.rotation_offset.cos()
-                };
-                ctx.draw(&Points {
-                    coords: &[(x, positions[i + y_component] as f64)],
-                    color: Color::Yellow,
-                });
-            }
-            _ => {}
-        }
+        //highlight picked points
+        let picked: Vec<(f64, f64)> = app.picked.iter().filter_map(|&i| points[i as usize]).collect();
+        ctx.draw(&Points {
+            coords: &picked,
+            color: Color::LightCyan,
+        });
+        //highlight selected points
+        let selected: Vec<(f64, f64)> = app.vertices.state.selected().iter().chain(&app.vertices.marked).filter_map(|&i| points[i]).collect();
+        ctx.draw(&Points {
+            coords: &selected,
+            color: Color::Yellow,
+        });
     });
 
     f.render_widget(viewport, area);
 }
 
-fn line_render<B>(f: &mut Frame<B>, app: &mut App, area: Rect) //render wireframe
+fn line_render<B>(f: &mut Frame<B>, app: &mut App, camera: &Camera, block: Block, area: Rect) //render wireframe
 where
     B: Backend,
 {
-    let x_zoom = app.zoom;
-    let y_zoom = app.zoom * 2.0 * area.height as f64 / area.width as f64;
-    let (y_component, z_component) = match app.top_down {
-        false => (1, 2),
-        true  => (2, 1),
-    };
+    let (x_bounds, y_bounds) = camera.bounds(area);
     
     let mut viewport = Canvas::default()
-    	.block(Block::default().title("Viewport").borders(Borders::ALL))
-    	.x_bounds([x_zoom * -1.0 + app.x_offset, x_zoom + app.x_offset])
-    	.y_bounds([y_zoom * -1.0 + app.y_offset, y_zoom + app.y_offset]);
+    	.marker(app.marker)
+    	.block(block)
+    	.x_bounds(x_bounds)
+    	.y_bounds(y_bounds);
     
+    let positions = view_positions(camera, &app.vertices.items);
+    let faces = &app.faces.items;
+    let face_edges = |face: &Face| (0..face.vertices.len()).map(|i| {
+        (face.vertices[i], face.vertices[(i + 1) % face.vertices.len()])
+    }).collect::<Vec<_>>();
+    let clipped = |(a, b): (u32, u32)| camera.project_edge(positions[a as usize], positions[b as usize])
+        .and_then(|(a, b)| clip_line(a, b, x_bounds, y_bounds));
+
+    let lines: Vec<Segment> = match app.hidden_lines {
+        true  => {
+            let inner = Block::default().borders(Borders::ALL).inner(area);
+            let (dots_x, dots_y) = match app.marker {
+                Marker::Braille => (2, 4),
+                _ => (1, 1),
+            };
+            let depth_area = (inner.width as usize * dots_x, inner.height as usize * dots_y);
+            visible_edges(camera, &positions, faces, app.crease_angle, depth_area, x_bounds, y_bounds)
+        },
+        false => faces.iter().flat_map(face_edges).filter_map(clipped).collect(),
+    };
+    let selected: Vec<Segment> = app.faces.state.selected().iter().chain(&app.faces.marked)
+        .flat_map(|&i| face_edges(&faces[i])).filter_map(clipped).collect();
+
     //draw lines between each vertex of each face
     viewport = viewport.paint(|ctx| {
-        let positions = &app.vertices.items;
-        let indices = &app.faces.items;
+        let mut draw_lines = |lines: &[Segment], color: Color| {
+            for &((x1, y1), (x2, y2)) in lines {
+                ctx.draw(&Line {
+                    x1,
+                    x2,
+                    y1,
+                    y2,
+                    color,
+                });
+            }
+        };
         
-        for i in 0..indices.len() {
-            let j = match i % 3 {
-                2 => i - 2,
-                _ => i + 1,
-            };
-            let f1 = indices[i] as usize * 3;
-            let f2 = indices[j] as usize * 3;
-            let x1 = {
-                positions[f1] as f64 * 
-                app.rotation_offset.sin() + 
-                positions[f1 + z_component] as f64 * 
-                app.rotation_offset.cos()
-            };
-            let x2 = {
-                positions[f2] as f64 * 
-                app.rotation_offset.sin() + 
-                positions[f2 + z_component] as f64 * 
-                app.rotation_offset.cos()
-            };
-            let y1 = positions[f1 + y_component] as f64;
-            let y2 = positions[f2 + y_component] as f64;
-            
-            ctx.draw(&Line {
-                x1: x1,
-                x2: x2,
-                y1: y1,
-                y2: y2,
-                color: Color::White,
-            });
-        }
+        draw_lines(&lines, Color::White);
         
         //highlight selected edges
-        match app.faces.state.selected() {
-            Some(value) => {
-                for i in 0..3 {
-                    let j = match i % 3 {
-                        2 => i - 2,
-                        _ => i + 1,
-                    };
-                    let f1 = indices[value * 3 + i] as usize * 3;
-                    let f2 = indices[value * 3 + j] as usize * 3;
-                    let x1 = {
-                        positions[f1] as f64 * 
-                        app.rotation_offset.sin() + 
-                        positions[f1 + z_component] as f64 * 
-                        app.rotation_offset.cos()
-                    };
-                    let x2 = {
-                        positions[f2] as f64 * 
-                        app.rotation_offset.sin() + 
-                        positions[f2 + z_component] as f64 * 
-                        app.rotation_offset.cos()
-                    };
-                    let y1 = positions[f1 + y_component] as f64;
-                    let y2 = positions[f2 + y_component] as f64;
-            
-                    ctx.draw(&Line {
-                        x1: x1,
-                        x2: x2,
-                        y1: y1,
-                        y2: y2,
-                        color: Color::Yellow,
-                    });
+        draw_lines(&selected, Color::Yellow);
+    });
+
+    f.render_widget(viewport, area);
+}
+
+fn visible_edges(camera: &Camera, positions: &[[f64; 3]], faces: &[Face], crease_angle: f64, (width, height): (usize, usize), x_bounds: [f64; 2], y_bounds: [f64; 2]) -> Vec<Segment> { //canvas lines of the outline and creases left uncovered by nearer faces
+    //faces on each side of every edge
+    let mut edges: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+    for (i, face) in faces.iter().enumerate() {
+        for k in 0..face.vertices.len() {
+            let (a, b) = (face.vertices[k], face.vertices[(k + 1) % face.vertices.len()]);
+            edges.entry((a.min(b), a.max(b))).or_default().push(i);
+        }
+    }
+    let mut depth = DepthBuffer::new(width, height, x_bounds, y_bounds);
+    let mut covering = vec![false; faces.len()];
+    for (corners, face) in view_triangles(camera, positions, faces) {
+        covering[face] |= depth.fill(&corners, face);
+    }
+
+    //whatever their winding, faces nearest the eye somewhere are turned to it and the other faces covering pixels are not;
+    //only faces too small to cover one go by winding, and normals are compared turned to the eye
+    let mut towards: Vec<bool> = faces.iter().enumerate().map(|(i, face)| !covering[i] && face_towards(camera, positions, face)).collect();
+    for &i in depth.triangles.iter().flatten() {
+        towards[i] = true;
+    }
+    let normals = face_normals(camera, positions, faces);
+
+    //keep open borders and silhouettes of faces turned to the eye, and creases between them
+    let outline = |sides: &[usize]| match sides.iter().filter(|&&i| towards[i]).count() {
+        0 => false,
+        n if n < sides.len() || sides.len() == 1 => true,
+        _ => sides.iter().any(|&i| dot(normals[i], normals[sides[0]]).clamp(-1.0, 1.0).acos() > crease_angle),
+    };
+    let nearness: Vec<f64> = positions.iter().filter(|&&v| camera.project(v).is_some()).map(|&v| camera.project_point(v)[2]).collect();
+    let range = nearness.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b)) - nearness.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+    let tolerance = range.max(0.0) * HIDDEN_TOLERANCE;
+
+    //walk each edge a pixel at a time, keeping the runs not behind the depth buffer
+    let mut lines = Vec::new();
+    for (&(a, b), sides) in &edges {
+        if !outline(sides) {
+            continue;
+        }
+        let Some((a, b)) = camera.clip_edge(positions[a as usize], positions[b as usize]) else { continue };
+        let (pa, pb) = (camera.project_point(a), camera.project_point(b));
+        let ((ax, ay), (bx, by)) = (depth.pixel(pa[0], pa[1]), depth.pixel(pb[0], pb[1]));
+        let steps = (bx - ax).abs().max((by - ay).abs()).min((width + height) as f64 * 4.0).ceil().max(1.0) as usize;
+        let at = |t: f64| (pa[0] + (pb[0] - pa[0]) * t, pa[1] + (pb[1] - pa[1]) * t);
+        let mut start = None;
+        for k in 0..=steps {
+            let t = k as f64 / steps as f64;
+            let visible = pa[2] + (pb[2] - pa[2]) * t >= depth.farthest_around(ax + (bx - ax) * t, ay + (by - ay) * t) - tolerance;
+            match (visible, start) {
+                (true, None) => start = Some(t),
+                (false, Some(from)) => {
+                    lines.extend(clip_line(at(from), at((k - 1) as f64 / steps as f64), x_bounds, y_bounds));
+                    start = None;
+                },
+                _ => {}
+            }
+        }
+        if let Some(from) = start {
+            lines.extend(clip_line(at(from), at(1.0), x_bounds, y_bounds));
+        }
+    }
+    lines
+}
+
+fn solid_render<B>(f: &mut Frame<B>, app: &mut App, camera: &Camera, block: Block, area: Rect) //render shaded faces
+where
+    B: Backend,
+{
+    let (x_bounds, y_bounds) = camera.bounds(area);
+    let positions = view_positions(camera, &app.vertices.items);
+    let light = normalize(LIGHT);
+
+    //shade each face by the angle between the light and its side facing the eye
+    let selected = |face: usize| app.tab_index == 1 && (app.faces.state.selected() == Some(face) || app.faces.marked.contains(&face));
+    let normals = face_normals(camera, &positions, &app.faces.items);
+    let triangles = view_triangles(camera, &positions, &app.faces.items).into_iter().map(|(corners, face)| {
+        let brightness = AMBIENT + (1.0 - AMBIENT) * dot(normals[face], light).max(0.0);
+        (corners, brightness, selected(face))
+    }).collect();
+
+    //mark picked and selected vertices in the vertex tab
+    let mut markers = Vec::new();
+    if app.tab_index == 0 {
+        let points: Vec<Option<(f64, f64)>> = positions.iter().map(|&v| camera.project(v)).collect();
+        markers.extend(app.picked.iter().filter_map(|&i| points[i as usize]).map(|p| (p, Color::LightCyan)));
+        markers.extend(app.vertices.state.selected().iter().chain(&app.vertices.marked).filter_map(|&i| points[i]).map(|p| (p, Color::Yellow)));
+    }
+
+    f.render_widget(Solid { block, x_bounds, y_bounds, triangles, markers, ascii: !matches!(app.marker, Marker::Braille) }, area);
+}
+
+struct Solid<'b> { //filled triangles drawn through a depth buffer, in half blocks or ascii shades
+    block: Block<'b>,
+    x_bounds: [f64; 2],
+    y_bounds: [f64; 2],
+    triangles: Vec<([[f64; 3]; 3], f64, bool)>, //canvas x, y and nearness of the corners, brightness, highlight
+    markers: Vec<((f64, f64), Color)>, //points drawn over the faces
+    ascii: bool,
+}
+
+impl Widget for Solid<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let inner = self.block.inner(area);
+        self.block.render(area, buf);
+        if inner.width == 0 || inner.height == 0 {
+            return;
+        }
+
+        //half blocks split each cell into an upper and a lower pixel
+        let rows = if self.ascii { 1 } else { 2 };
+        let (width, height) = (inner.width as usize, inner.height as usize * rows);
+        let mut depth = DepthBuffer::new(width, height, self.x_bounds, self.y_bounds);
+        for (i, (corners, _, _)) in self.triangles.iter().enumerate() {
+            depth.fill(corners, i);
+        }
+        let pixels: Vec<Option<(f64, bool)>> = depth.triangles.iter().map(|t| t.map(|i| (self.triangles[i].1, self.triangles[i].2))).collect();
+
+        let colour = |(brightness, highlight): (f64, bool)| {
+            let level = (brightness * 255.0) as u8;
+            match highlight {
+                true  => Color::Rgb(level, level, 0),
+                false => Color::Rgb(level, level, level),
+            }
+        };
+        for row in 0..inner.height as usize {
+            for column in 0..width {
+                let cell = buf.get_mut(inner.left() + column as u16, inner.top() + row as u16);
+                if self.ascii {
+                    if let Some((brightness, highlight)) = pixels[row * width + column] {
+                        let shade = SHADES[((brightness * SHADES.len() as f64) as usize).min(SHADES.len() - 1)];
+                        cell.set_char(shade as char).set_fg(if highlight { Color::Yellow } else { Color::White });
+                    }
+                    continue;
                 }
+                match (pixels[2 * row * width + column], pixels[(2 * row + 1) * width + column]) {
+                    (Some(upper), Some(lower)) => cell.set_char('▀').set_fg(colour(upper)).set_bg(colour(lower)),
+                    (Some(upper), None) => cell.set_char('▀').set_fg(colour(upper)),
+                    (None, Some(lower)) => cell.set_char('▄').set_fg(colour(lower)),
+                    (None, None) => continue,
+                };
             }
-            _ => {}
         }
-    });
 
-    f.render_widget(viewport, area);
+        for ((x, y), colour) in self.markers {
+            let (x, y) = depth.pixel(x, y);
+            if (0.0..width as f64).contains(&x) && (0.0..height as f64).contains(&y) {
+                buf.get_mut(inner.left() + x as u16, inner.top() + (y as usize / rows) as u16).set_char('•').set_fg(colour);
+            }
+        }
+    }
+}
+
+struct DepthBuffer { //nearest triangle at each pixel of a view
+    width: usize,
+    height: usize,
+    x_bounds: [f64; 2],
+    y_bounds: [f64; 2],
+    nearest: Vec<f64>, //nearness of the surface, or negative infinity where nothing was drawn
+    triangles: Vec<Option<usize>>, //index of the triangle drawn
+}
+
+impl DepthBuffer {
+    fn new(width: usize, height: usize, x_bounds: [f64; 2], y_bounds: [f64; 2]) -> DepthBuffer {
+        DepthBuffer {
+            width,
+            height,
+            x_bounds,
+            y_bounds,
+            nearest: vec![f64::NEG_INFINITY; width * height],
+            triangles: vec![None; width * height],
+        }
+    }
+
+    fn pixel(&self, x: f64, y: f64) -> (f64, f64) { //pixel position of a canvas point, counting from the top left
+        (
+            (x - self.x_bounds[0]) / (self.x_bounds[1] - self.x_bounds[0]) * self.width as f64,
+            (self.y_bounds[1] - y) / (self.y_bounds[1] - self.y_bounds[0]) * self.height as f64,
+        )
+    }
+
+    fn fill(&mut self, corners: &[[f64; 3]; 3], index: usize) -> bool { //draw a triangle given by canvas x, y and nearness, keeping the nearest surface; false when it covers no pixel
+        let p = corners.map(|c| {
+            let (x, y) = self.pixel(c[0], c[1]);
+            [x, y, c[2]]
+        });
+        let area = (p[1][0] - p[0][0]) * (p[2][1] - p[0][1]) - (p[2][0] - p[0][0]) * (p[1][1] - p[0][1]);
+        if area.abs() < 1e-12 {
+            return false;
+        }
+        let left = p.iter().map(|c| c[0]).fold(f64::INFINITY, f64::min).max(0.0).floor() as usize;
+        let right = p.iter().map(|c| c[0]).fold(f64::NEG_INFINITY, f64::max).min(self.width as f64).ceil() as usize;
+        let top = p.iter().map(|c| c[1]).fold(f64::INFINITY, f64::min).max(0.0).floor() as usize;
+        let bottom = p.iter().map(|c| c[1]).fold(f64::NEG_INFINITY, f64::max).min(self.height as f64).ceil() as usize;
+        let mut covered = false;
+        for y in top..bottom {
+            for x in left..right {
+                //barycentric weights of the pixel centre
+                let (cx, cy) = (x as f64 + 0.5, y as f64 + 0.5);
+                let w = [(1, 2), (2, 0), (0, 1)].map(|(a, b)| {
+                    ((p[b][0] - p[a][0]) * (cy - p[a][1]) - (cx - p[a][0]) * (p[b][1] - p[a][1])) / area
+                });
+                if w.iter().any(|&w| w < 0.0) {
+                    continue;
+                }
+                covered = true;
+                let nearness = w[0] * p[0][2] + w[1] * p[1][2] + w[2] * p[2][2];
+                let i = y * self.width + x;
+                if nearness > self.nearest[i] {
+                    self.nearest[i] = nearness;
+                    self.triangles[i] = Some(index);
+                }
+            }
+        }
+        covered
+    }
+
+    fn farthest_around(&self, x: f64, y: f64) -> f64 { //nearness of the farthest surface at and next to a pixel, so lines lying on a surface stay visible
+        let (x, y) = (x.floor() as isize, y.floor() as isize);
+        let mut farthest = f64::INFINITY;
+        for py in y - 1..=y + 1 {
+            for px in x - 1..=x + 1 {
+                let nearness = match (0..self.width as isize).contains(&px) && (0..self.height as isize).contains(&py) {
+                    true  => self.nearest[py as usize * self.width + px as usize],
+                    false => f64::NEG_INFINITY,
+                };
+                farthest = farthest.min(nearness);
+            }
+        }
+        farthest
+    }
+}
+
+fn view_triangles(camera: &Camera, positions: &[[f64; 3]], faces: &[Face]) -> Vec<([[f64; 3]; 3], usize)> { //canvas x, y and nearness of every face fanned into triangles, with the face index
+    let mut triangles = Vec::new();
+    for (i, face) in faces.iter().enumerate() {
+        let corners: Vec<[f64; 3]> = face.vertices.iter().map(|&v| positions[v as usize]).collect();
+        let projected = camera.project_polygon(&corners);
+        for k in 1..projected.len().saturating_sub(1) {
+            triangles.push(([projected[0], projected[k], projected[k + 1]], i));
+        }
+    }
+    triangles
+}
+
+fn face_normals(camera: &Camera, positions: &[[f64; 3]], faces: &[Face]) -> Vec<[f64; 3]> { //view space unit normals turned towards the eye; zero for faces with no area
+    faces.iter().map(|face| {
+        let normal = face_normal(positions, face);
+        match face_towards(camera, positions, face) {
+            true  => normal,
+            false => normal.map(|c| -c),
+        }
+    }).collect()
+}
+
+fn face_normal(positions: &[[f64; 3]], face: &Face) -> [f64; 3] { //unit normal by the right-hand rule from the first three corners
+    if face.vertices.len() < 3 {
+        return [0.0; 3];
+    }
+    let corner = |k: usize| positions[face.vertices[k] as usize];
+    normalize(cross(sub(corner(1), corner(0)), sub(corner(2), corner(0))))
+}
+
+fn face_towards(camera: &Camera, positions: &[[f64; 3]], face: &Face) -> bool { //whether the front of a face, by its winding, is turned to the eye
+    match face.vertices.first() {
+        Some(&v) => dot(face_normal(positions, face), camera.eye_direction(positions[v as usize])) >= 0.0,
+        None => false,
+    }
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn normalize(v: [f64; 3]) -> [f64; 3] { //unit vector; zero stays zero
+    let length = dot(v, v).sqrt();
+    match length > 0.0 {
+        true  => v.map(|c| c / length),
+        false => v,
+    }
+}
+
+fn viewport_title(camera: &Camera) -> String { //viewport name and projection, so a view can be described to others
+    let view = camera.view_name().unwrap_or("Free");
+    match camera.perspective {
+        true => format!("Viewport - {} (perspective {:.0}°)", view, camera.fov.to_degrees()),
+        false => format!("Viewport - {}", view),
+    }
 }
 
+fn view_positions(camera: &Camera, vertices: &[Vertex]) -> Vec<[f64; 3]> { //positions of vertices relative to the camera, before projection
+    let matrix = camera.matrix();
+    vertices.iter().map(|vertex| {
+        let p = vertex.position;
+        rotate(&matrix, [0, 1, 2].map(|i| p[i] as f64 - camera.target[i]))
+    }).collect()
+}
 
+fn clip_line(a: (f64, f64), b: (f64, f64), x_bounds: [f64; 2], y_bounds: [f64; 2]) -> Option<Segment> { //part of a line inside the canvas bounds, since the canvas skips lines leaving it
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let (mut enter, mut leave) = (0.0f64, 1.0f64);
+    for (p, q) in [(-dx, a.0 - x_bounds[0]), (dx, x_bounds[1] - a.0), (-dy, a.1 - y_bounds[0]), (dy, y_bounds[1] - a.1)] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            enter = enter.max(q / p);
+        } else {
+            leave = leave.min(q / p);
+        }
+    }
+    if enter > leave {
+        return None;
+    }
+    let point = |t: f64| (
+        (a.0 + dx * t).clamp(x_bounds[0], x_bounds[1]),
+        (a.1 + dy * t).clamp(y_bounds[0], y_bounds[1]),
+    );
+    Some((point(enter), point(leave)))
+}
 
-fn draw_help<B>(f: &mut Frame<B>, app: &mut App, area: Rect) //help menu, stored in compiled program as string literal
+fn draw_help<B>(f: &mut Frame<B>, _app: &mut App, area: Rect) //help menu, stored in compiled program as string literal
 where
     B: Backend,
 {
@@ -796,11 +3417,60 @@ where
 \n
     F | Face Mode   - Switch to face editing interface\n
 \n
-    O | Open File   - Open .OBJ or .STL file\n
+    O | Open File   - Open .OBJ or .STL file; Tab completes the path, Down browses for it\n
+\n
+    W | Write File  - Save models as .OBJ or .STL file\n
+\n
+    : | Command     - Type a command such as open, write, goto v 120, scale 2 or set zoom 5\n
+                      Tab completes, Up/Down recall earlier commands, help lists them all\n
+\n
+    Left/Right      - Switch between the models of a file\n
+\n
+    U | Undo        - Revert the last edit\n
+\n
+    R | Redo        - Repeat the last undone edit\n
+\n
+Viewport (Vertex and Face Mode)\n
+    7/9 | Yaw       - Turn the view about the vertical axis\n
+\n
+    1/3 | Pitch     - Tilt the view up or down\n
+\n
+    / * | Roll      - Turn the view about the viewing direction\n
+\n
+    8/2/4/6 | Pan   - Move the view, +/- to zoom, 5 to toggle the top view\n
+\n
+    Alt+5/0/4/6/8/2 | Views - Front, back, left, right, top or bottom; Alt+9 isometric\n
+\n
+    S | Solid       - Toggle filled, shaded faces\n
+\n
+    Mouse           - Drag to orbit, right or middle drag to pan, wheel to zoom; click a row or the model to select\n
+\n
+    Space | Mark    - Add the selected item to the selection set, or take it out; Esc empties it\n
+                      Shift-click a row, or shift-drag a box in the viewport, to add more\n
+\n
+    E | Edges       - Toggle hidden line removal; { } change the crease angle\n
+\n
+    L | Layout      - Toggle the quad view; C moves the controls to the next pane\n
+\n
+    Home / . | Frame - Fit the view to the model or to the selection\n
+\n
+    0 | Perspective - Toggle perspective; [ ] change the field of view, +/- move the eye\n
 \n
 Vertex Mode\n
+    N | New Vertex  - Add a vertex at typed coordinates\n
 \n
-Faces Mode"
+    D | Delete      - Remove the selected vertex and the faces using it\n
+\n
+    P | Pick        - Toggle the selected vertex as a corner for a new face\n
+\n
+    T | Transform   - Move, rotate or scale the selected vertex (Esc: whole model)\n
+\n
+Faces Mode\n
+    N | New Face    - Add a face from typed or picked vertex numbers\n
+\n
+    D | Delete      - Remove the selected face\n
+\n
+    T | Transform   - Move, rotate or scale the selected face (Esc: whole model)"
         )
         .style(Style::default().fg(Color::White))
         .alignment(Alignment::Left)
@@ -814,3 +3484,514 @@ Faces Mode"
     
     f.render_widget(help, area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(text: &str) -> String {
+        write_obj(&parse_obj(text, "test").unwrap())
+    }
+
+    fn open_text(name: &str, text: &str) -> App<'static> { //app with a file holding some text opened
+        let path = std::env::temp_dir().join(format!("tui_obj_{}_{}", std::process::id(), name));
+        fs::write(&path, text).unwrap();
+        let mut app = App::new();
+        app.open_file(&path.to_string_lossy());
+        assert!(app.status.starts_with("Opened"), "{}", app.status);
+        fs::remove_file(&path).unwrap();
+        app
+    }
+
+    fn written_text(app: &mut App, name: &str) -> String { //what writing the open models puts in a file
+        let path = std::env::temp_dir().join(format!("tui_obj_{}_{}", std::process::id(), name));
+        app.write_file(&path.to_string_lossy());
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        text
+    }
+
+    #[test]
+    fn samples_round_trip_unchanged() {
+        for name in ["cube", "teapot", "teddy"] {
+            let text = fs::read_to_string(format!("sample/{}.obj", name)).unwrap();
+            assert!(round_trip(&text) == text, "{} changed on writing", name);
+        }
+    }
+
+    #[test]
+    fn layout_round_trips_unchanged() {
+        let texts = [
+            "v 0 0 0\r\nv 1 0 0\r\nv 0 1 0\r\nf 1 2 3\r\n",
+            "# no final newline\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3",
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nf -4 -3 -1 -2\n",
+            "mtllib a.mtl\no quad\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 1 1\nvn 0 0 1\nusemtl red\ns 1\nf 1/1/1 2/2/1 3/3/1 4/1/1\ng side\nf 1//1 2//1 3//1\nf 1/1 3/2 4/3\n",
+            "o a\nv 0 0 0\nv 1 0 0\nv 0 1 0\no b\nv 0 0 1\nf 1 2 4\nf 1 2 3\n",
+            "o\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n",
+        ];
+        for text in texts {
+            assert_eq!(round_trip(text), text);
+        }
+    }
+
+    #[test]
+    fn faces_keep_their_corners() {
+        let models = parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nvt 0 0\nvn 0 0 1\nf -4/1/1 -3/1/1 -1/-1/-1 -2/1/1\n", "test").unwrap();
+        let face = &models[0].faces[0];
+        assert_eq!(face.vertices, [0, 1, 3, 2]);
+        assert_eq!(face.texcoords, [0; 4]);
+        assert_eq!(face.normals, [0; 4]);
+    }
+
+    #[test]
+    fn objects_sharing_vertices_stay_together() {
+        let models = parse_obj("o a\nv 0 0 0\nv 1 0 0\nv 0 1 0\no b\nv 0 0 1\nf 1 2 4\n", "test").unwrap();
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].vertices.len(), 4);
+        let models = parse_obj("o a\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\no b\nv 0 0 1\n", "test").unwrap();
+        assert_eq!(models.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), ["a", "b"]);
+    }
+
+    #[test]
+    fn bare_object_statement_is_kept() {
+        let models = parse_obj("o\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n", "stem").unwrap();
+        assert_eq!(models[0].name, "stem");
+        assert!(write_obj(&models).starts_with("o\n"));
+    }
+
+    #[test]
+    fn moved_vertex_keeps_extra_values() {
+        let mut models = parse_obj("v 0 0 0 1.0 0.5 0.25 0.125\nv 1 0 0\n", "test").unwrap();
+        models[0].vertices[0].position = [2.0, 0.0, 0.0];
+        assert_eq!(write_obj(&models), "v 2 0 0 1.0 0.5 0.25 0.125\nv 1 0 0\n");
+    }
+
+    #[test]
+    fn edit_then_undo_writes_the_file_unchanged() {
+        let text = fs::read_to_string("sample/teapot.obj").unwrap();
+        let mut app = open_text("undo.obj", &text);
+        app.backup();
+        app.apply(Change::MoveVertex(0, [9.0, 9.0, 9.0]));
+        app.backup();
+        app.apply(Change::RemoveFace(3));
+        assert!(written_text(&mut app, "undo_edited.obj") != text);
+        app.restore();
+        app.restore();
+        assert!(written_text(&mut app, "undo_restored.obj") == text);
+    }
+
+    #[test]
+    fn undo_and_redo_restore_the_lists_exactly() {
+        let mut app = App::new();
+        let original = (app.vertices.items.clone(), app.faces.items.clone());
+        app.backup();
+        app.apply(Change::InsertVertex(8, Vertex::new([3.0, 4.0, 5.0])));
+        app.apply(Change::MoveVertex(0, [2.0, 2.0, 2.0]));
+        app.backup();
+        app.apply(Change::SetFace(0, Face::new(vec![0, 1, 8])));
+        app.apply(Change::RemoveFace(4));
+        let edited = (app.vertices.items.clone(), app.faces.items.clone());
+
+        for _ in 0..2 {
+            app.restore();
+        }
+        assert!((app.vertices.items.clone(), app.faces.items.clone()) == original);
+        for _ in 0..2 {
+            app.redo();
+        }
+        assert!((app.vertices.items.clone(), app.faces.items.clone()) == edited);
+        assert!(app.future.is_empty());
+    }
+
+    #[test]
+    fn deleting_a_vertex_renumbers_faces() {
+        let mut app = App::new();
+        app.tab_index = 0;
+        app.vertices.state.select(Some(1));
+        app.delete_item().unwrap();
+        assert_eq!(app.vertices.items.len(), 7);
+        //the cube's faces not using v2, with every later vertex moved down one
+        let faces: Vec<Vec<u32>> = app.faces.items.iter().map(|face| face.vertices.clone()).collect();
+        assert_eq!(faces, [vec![0, 2, 1], vec![3, 4, 5], vec![3, 5, 6], vec![0, 4, 3], vec![1, 2, 6], vec![1, 6, 5], vec![2, 0, 3], vec![2, 3, 6]]);
+    }
+
+    #[test]
+    fn transform_errors_are_reported() {
+        let error = |input: &str| parse_transform(input).err().unwrap();
+        assert_eq!(error("t 1 2"), "translate takes x y z");
+        assert_eq!(error("t 1 two 3"), "invalid number: two");
+        assert_eq!(error("r w 90"), "unknown axis: w");
+        assert_eq!(error("r 0 0 0 90"), "rotation axis cannot be zero");
+        assert_eq!(error("r x"), "rotate takes an axis (x, y, z or a vector) and degrees");
+        assert_eq!(error("s 1 2"), "scale takes one factor or x y z");
+        assert_eq!(error("spin 3"), "unknown transform; use t x y z, r axis degrees or s factor");
+        assert!(matches!(parse_transform("scale 2"), Ok(Transform::Scale([2.0, 2.0, 2.0]))));
+    }
+
+    fn binary_stl(header: &[u8], triangles: &[[[f32; 3]; 3]]) -> Vec<u8> { //binary stl with zero normals
+        let mut data = header.to_vec();
+        data.resize(80, 0);
+        data.extend((triangles.len() as u32).to_le_bytes());
+        for triangle in triangles {
+            data.extend([0; 12]);
+            for value in triangle.iter().flatten() {
+                data.extend(value.to_le_bytes());
+            }
+            data.extend([0, 0]);
+        }
+        data
+    }
+
+    #[test]
+    fn ascii_stl_cube_is_welded() {
+        let model = parse_stl(&fs::read("sample/cube.stl").unwrap(), "cube").unwrap();
+        assert_eq!((model.vertices.len(), model.faces.len()), (8, 12));
+    }
+
+    #[test]
+    fn binary_stl_may_start_with_solid() {
+        let data = binary_stl(b"solid but binary", &[[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]]);
+        let model = parse_stl(&data, "test").unwrap();
+        assert_eq!((model.vertices.len(), model.faces.len()), (3, 1));
+        assert_eq!(model.vertices[1].position, [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn truncated_stl_is_an_error() {
+        let data = binary_stl(b"", &[[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]; 2]);
+        assert!(parse_stl(&data[..data.len() - 10], "test").is_err());
+        assert!(parse_stl(&data[..40], "test").is_err());
+
+        let text = fs::read_to_string("sample/cube.stl").unwrap();
+        let cut = text.find("endloop").unwrap() - 1;
+        assert!(parse_stl(&text.as_bytes()[..cut], "test").is_err());
+        let cut = text.find("vertex").unwrap() + 10;
+        assert!(parse_stl(&text.as_bytes()[..cut], "test").is_err());
+    }
+
+    #[test]
+    fn negative_zero_welds_with_zero() {
+        let data = binary_stl(b"", &[
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            [[-0.0, -0.0, 0.0], [0.0, -1.0, 0.0], [1.0, -0.0, -0.0]],
+        ]);
+        let model = parse_stl(&data, "test").unwrap();
+        assert_eq!(model.vertices.len(), 4);
+        assert_eq!(model.faces[1].vertices, [0, 3, 1]);
+    }
+
+    #[test]
+    fn written_stl_reads_back() {
+        let cube = parse_stl(&fs::read("sample/cube.stl").unwrap(), "cube").unwrap();
+        for binary in [false, true] {
+            let data = write_stl(std::slice::from_ref(&cube), binary);
+            assert_eq!(data.starts_with(b"solid cube\n"), !binary);
+            let model = parse_stl(&data, "cube").unwrap();
+            assert_eq!((model.vertices.len(), model.faces.len()), (8, 12));
+            assert!(model_triangles(&model) == model_triangles(&cube));
+        }
+    }
+
+    #[test]
+    fn stl_export_keeps_the_path_to_save_to() {
+        let mut app = open_text("export.obj", &fs::read_to_string("sample/cube.obj").unwrap());
+        let path = app.path.clone();
+        let stl = std::env::temp_dir().join(format!("tui_obj_{}_export.stl", std::process::id()));
+        app.write_stl_file(&stl.to_string_lossy(), true);
+        fs::remove_file(&stl).unwrap();
+        assert!(app.status.starts_with("Wrote binary STL file"), "{}", app.status);
+        assert_eq!(app.path, path);
+    }
+
+    #[test]
+    fn facet_normals_follow_the_winding() {
+        assert_eq!(triangle_normal([0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 2.0, 0.0]), [0.0, 0.0, 1.0]);
+        assert_eq!(triangle_normal([0.0, 0.0, 0.0], [0.0, 2.0, 0.0], [2.0, 0.0, 0.0]), [0.0, 0.0, -1.0]);
+        assert_eq!(triangle_normal([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [2.0, 0.0, 0.0]), [0.0; 3]);
+    }
+
+    fn parse(line: &str) -> Result<Options, String> {
+        parse_options(&line.split_whitespace().map(str::to_string).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn options_take_their_values() {
+        let options = parse("model.obj -t face -z 3 --zoom-step 1.5 -r 90 -p 45 --roll -30 -s -e --crease 40 -P --fov 90 --distance 5 --near 0.5 -W 40 -H 12 --ascii").unwrap();
+        assert_eq!(options.path.as_deref(), Some("model.obj"));
+        assert_eq!(options.tab, Some(1));
+        assert_eq!((options.zoom, options.zoom_step), (Some(3.0), Some(1.5)));
+        assert_eq!((options.yaw, options.pitch, options.roll), (Some(90f64.to_radians()), Some(45f64.to_radians()), Some(-30f64.to_radians())));
+        assert_eq!((options.crease, options.fov), (Some(40f64.to_radians()), Some(90f64.to_radians())));
+        assert_eq!((options.distance, options.near), (Some(5.0), Some(0.5)));
+        assert_eq!((options.width, options.height), (Some(40), Some(12)));
+        assert!(options.solid && options.hidden_lines && options.perspective && options.ascii);
+
+        let options = parse("").unwrap();
+        assert!(options.path.is_none() && options.tab.is_none() && options.zoom.is_none());
+        assert!(!options.solid && !options.hidden_lines && !options.perspective && !options.ascii);
+    }
+
+    #[test]
+    fn option_errors_name_the_option() {
+        let error = |line: &str| parse(line).err().unwrap();
+        assert_eq!(error("-z"), "-z needs a value");
+        assert_eq!(error("--zoom x"), "invalid number for --zoom: x");
+        assert_eq!(error("-z 0"), "-z must be positive");
+        assert_eq!(error("--near -1"), "--near must be positive");
+        assert_eq!(error("--zoom-step 1"), "zoom step must be greater than 1");
+        assert_eq!(error("--fov 4"), "fov must be between 5 and 170 degrees");
+        assert_eq!(error("--fov 171"), "fov must be between 5 and 170 degrees");
+        assert_eq!(error("--crease 181"), "crease must be between 0 and 180 degrees");
+        assert_eq!(error("-t edges"), "unknown tab: edges");
+        assert_eq!(error("-V sideways"), "unknown view: sideways");
+        assert_eq!(error("-W 0"), "invalid size for -W: 0");
+        assert_eq!(error("-H 70000"), "invalid size for -H: 70000");
+        assert_eq!(error("--frobnicate"), "unknown option: --frobnicate");
+        assert_eq!(error("a.obj b.obj"), "unexpected argument: b.obj");
+    }
+
+    #[test]
+    fn view_names_are_shared_by_options_and_commands() {
+        let view = |name: &str| {
+            let options = parse(&format!("-V {}", name)).unwrap();
+            (options.yaw, options.pitch)
+        };
+        assert_eq!(view("top"), (Some(0.0), Some(90f64.to_radians())));
+        assert_eq!(view("side"), view("Right"));
+        assert_eq!(view("iso"), view("isometric"));
+
+        let mut app = App::new();
+        for (name, shown) in [("side", "Right"), ("iso", "Isometric"), ("BACK", "Back")] {
+            app.execute(&format!("view {}", name));
+            assert_eq!(app.camera.view_name(), Some(shown));
+        }
+        app.execute("view sideways");
+        assert_eq!(app.status, "view: unknown view sideways (usage: view front|back|left|right|top|bottom|isometric)");
+    }
+
+    #[test]
+    fn turned_cube_render_matches_snapshot() {
+        let args = |extra: &[&str]| -> Vec<String> {
+            ["-W", "32", "-H", "10", "-z", "2", "-r", "30"].iter().chain(extra).map(|a| a.to_string()).collect()
+        };
+        let braille = [
+            "\n",
+            "    ⢰⢖⠒⠒⠒⠒⠒⠒⣶⠒⠒⠒⠒⠒⢲⡖⠒⠒⠒⠒⠒⢒⢶\n",
+            "    ⢸ ⠑⢄   ⡜⢸     ⢸⠘⡄  ⢀⠔⠁⢸\n",
+            "    ⢸   ⠑⢄⠎ ⢸     ⢸ ⠈⢆⠔⠁  ⢸\n",
+            "    ⢸   ⢀⠎⠑⢄⢸     ⢸⢀⠔⠉⢆   ⢸\n",
+            "    ⢸  ⢠⠃   ⢹⢄   ⢀⢼⠁   ⢣  ⢸\n",
+            "    ⢸ ⡠⠃    ⢸ ⠑⢄⠔⠁⢸     ⠣⡀⢸\n",
+            "    ⢸⡰⠁     ⢸⢀⠔⠁⠑⢄⢸      ⠱⣸\n",
+            "    ⠘⠓⠒⠒⠒⠒⠒⠒⠚⠓⠒⠒⠒⠒⠛⠒⠒⠒⠒⠒⠒⠒⠛\n",
+            "\n",
+        ];
+        assert_eq!(render_text(&args(&[])).unwrap(), braille.concat());
+        let ascii = [
+            "\n",
+            "    ***********************\n",
+            "    * **   **     ***  ** *\n",
+            "    *   *** *     *  **   *\n",
+            "    *   * ***     *** *   *\n",
+            "    *  *    *** ***    ** *\n",
+            "    ***     * *** *      **\n",
+            "    ***********************\n",
+            "\n",
+            "\n",
+        ];
+        assert_eq!(render_text(&args(&["--ascii"])).unwrap(), ascii.concat());
+    }
+
+    #[test]
+    fn hidden_line_render_matches_snapshot() {
+        let args = |path: &[&str]| -> Vec<String> {
+            path.iter().chain(&["-W", "32", "-H", "10", "-V", "iso", "-e"]).map(|a| a.to_string()).collect()
+        };
+        let braille = [
+            "             ⢀⡠⠔⠢⢄⡀\n",
+            "         ⢀⡠⠔⠊⠁    ⠈⠑⠢⢄⡀\n",
+            "      ⢠⢔⡊⠁            ⠈⢑⣢\n",
+            "      ⢸ ⠈⠑⠢⢄⡀      ⢀⡠⠔⠊⠁⢸\n",
+            "      ⢸     ⠈⠑⠢⢄⡠⠔⠊⠁    ⢸\n",
+            "      ⢸        ⢸        ⢸\n",
+            "      ⢸        ⢸        ⢸\n",
+            "      ⠈⠑⠢⢄⡀    ⢸     ⢀⡠⠔⠊\n",
+            "          ⠈⠑⠢⢄⡀⢸ ⢀⡠⠔⠊⠁\n",
+            "              ⠈⠙⠊⠁\n",
+        ];
+        assert_eq!(render_text(&args(&[])).unwrap(), braille.concat());
+
+        //the same cube with every face wound the other way draws the same
+        let app = App::new();
+        let mut obj = String::new();
+        for vertex in &app.vertices.items {
+            obj.push_str(&format!("v {} {} {}\n", vertex.position[0], vertex.position[1], vertex.position[2]));
+        }
+        for face in &app.faces.items {
+            obj.push_str(&format!("f {} {} {}\n", face.vertices[2] + 1, face.vertices[1] + 1, face.vertices[0] + 1));
+        }
+        let path = std::env::temp_dir().join(format!("tui_obj_{}_inside_out.obj", std::process::id()));
+        fs::write(&path, obj).unwrap();
+        let rendered = render_text(&args(&[&path.to_string_lossy()]));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(rendered.unwrap(), braille.concat());
+    }
+
+    fn clipping_camera() -> Camera { //perspective eye at z 10 with the near plane at z 9
+        Camera { perspective: true, distance: 10.0, near: 1.0, ..Camera::default() }
+    }
+
+    #[test]
+    fn edges_are_cut_at_the_near_plane() {
+        let camera = clipping_camera();
+        assert_eq!(camera.clip_edge([2.0, 0.0, 0.0], [2.0, 4.0, 12.0]), Some(([2.0, 0.0, 0.0], [2.0, 3.0, 9.0])));
+        assert_eq!(camera.clip_edge([2.0, 4.0, 12.0], [2.0, 0.0, 0.0]), Some(([2.0, 3.0, 9.0], [2.0, 0.0, 0.0])));
+        assert_eq!(camera.clip_edge([0.0, 0.0, 11.0], [1.0, 1.0, 15.0]), None);
+        assert_eq!(camera.clip_edge([0.0, 0.0, 9.5], [1.0, 0.0, 9.5]), None);
+        assert!(camera.project_edge([0.0, 0.0, 11.0], [1.0, 1.0, 15.0]).is_none());
+
+        let flat = Camera { perspective: false, ..camera };
+        assert_eq!(flat.clip_edge([0.0, 0.0, 11.0], [1.0, 1.0, 15.0]), Some(([0.0, 0.0, 11.0], [1.0, 1.0, 15.0])));
+    }
+
+    #[test]
+    fn polygons_are_cut_at_the_near_plane() {
+        let camera = clipping_camera();
+        let scale = camera.zoom / (camera.fov / 2.0).tan();
+        let expected = [[-2.0, 0.0, 10.0], [2.0, 0.0, 10.0], [0.5, 0.0, 1.0], [-0.5, 0.0, 1.0]].map(|[x, y, depth]| [x * scale / depth, y * scale / depth, 1.0 / depth]);
+        let corners = camera.project_polygon(&[[-2.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 0.0, 12.0]]);
+        assert_eq!(corners.len(), 4);
+        for (corner, expected) in corners.iter().zip(expected) {
+            assert!((0..3).all(|i| (corner[i] - expected[i]).abs() < 1e-9), "{:?} != {:?}", corner, expected);
+        }
+
+        assert!(camera.project_polygon(&[[0.0, 0.0, 11.0], [1.0, 0.0, 11.0], [0.0, 1.0, 12.0]]).is_empty());
+        assert_eq!(camera.project_polygon(&[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]).len(), 3);
+    }
+
+    #[test]
+    fn deleting_marked_vertices_renumbers_faces() {
+        let mut app = App::new();
+        let original = (app.vertices.items.clone(), app.faces.items.clone());
+        app.tab_index = 0;
+        app.vertices.marked.extend([1, 6]);
+        app.delete_marked();
+        assert_eq!(app.vertices.items.len(), 6);
+        //the cube's faces using neither v2 nor v7, with later vertices moved down past both
+        let faces: Vec<Vec<u32>> = app.faces.items.iter().map(|face| face.vertices.clone()).collect();
+        assert_eq!(faces, [vec![0, 2, 1], vec![0, 4, 3], vec![1, 2, 5], vec![2, 0, 3], vec![2, 3, 5]]);
+        app.restore();
+        assert!((app.vertices.items.clone(), app.faces.items.clone()) == original);
+    }
+
+    #[test]
+    fn writing_a_selection_keeps_only_faces_inside_it() {
+        let mut app = App::new();
+        app.tab_index = 0;
+        app.vertices.marked.extend([0, 1, 2, 4]);
+        let models = parse_obj(&written_text(&mut app, "selection.obj"), "test").unwrap();
+        assert_eq!(models[0].vertices.len(), 4);
+        let faces: Vec<Vec<u32>> = models[0].faces.iter().map(|face| face.vertices.clone()).collect();
+        assert_eq!(faces, [vec![0, 2, 1]]);
+        assert_eq!(app.path, "");
+
+        app.tab_index = 1;
+        app.faces.marked.extend([2, 3]);
+        let models = parse_obj(&written_text(&mut app, "selection.obj"), "test").unwrap();
+        assert_eq!(models[0].vertices.len(), 4);
+        assert_eq!(models[0].faces.len(), 2);
+    }
+
+    #[test]
+    fn commands_match_by_unique_prefix() {
+        assert_eq!(find_command("set").unwrap().name, "set");
+        assert_eq!(find_command("w").unwrap().name, "write");
+        assert_eq!(find_command("sc").unwrap().name, "scale");
+        assert_eq!(find_command("s").err().unwrap(), "Ambiguous command: s (scale, set)");
+        assert_eq!(find_command("frob").err().unwrap(), "Unknown command: frob");
+    }
+
+    #[test]
+    fn completions_cover_names_and_arguments() {
+        assert_eq!(completions("s"), (0, vec!["scale ".to_string(), "set ".to_string()]));
+        assert_eq!(completions("set zoom"), (4, vec!["zoom ".to_string(), "zoom-step ".to_string()]));
+        assert_eq!(completions("view b"), (5, vec!["back ".to_string(), "bottom ".to_string()]));
+        assert_eq!(completions("goto v 1"), (8, Vec::<String>::new()));
+
+        let mut app = App::new();
+        app.status_mode = StatusMode::Command;
+        app.status = "se".to_string();
+        app.complete();
+        assert_eq!(app.status, "set ");
+    }
+
+    #[test]
+    fn command_errors_name_the_usage() {
+        let mut app = App::new();
+        app.execute("goto f 99");
+        assert_eq!(app.status, "goto: no f99 (f1 to f12) (usage: goto [v|f] NUMBER)");
+        app.execute("set zoom -1");
+        assert_eq!(app.status, "set: zoom must be positive (usage: set OPTION [VALUE])");
+        app.execute("set zoom 5");
+        assert_eq!(app.camera.zoom, 5.0);
+        app.execute("scale x");
+        assert_eq!(app.status, "scale: invalid number: x (usage: scale FACTOR | X Y Z)");
+        app.execute("face 1 2 1");
+        assert_eq!(app.status, "face: invalid vertices: 1 2 1 (repeated vertex) (usage: face V1 V2 V3 ...)");
+        app.tab_index = 0;
+        app.execute("delete");
+        assert_eq!(app.status, "delete: no vertex selected (usage: delete)");
+    }
+
+    #[test]
+    fn prompt_edits_multibyte_text_at_the_cursor() {
+        let mut app = App::new();
+        app.status = "ab".to_string();
+        for c in "é✓".chars() {
+            app.edit_prompt(KeyCode::Char(c));
+        }
+        assert_eq!(app.status, "abé✓");
+
+        //backspace does nothing at the start and delete takes the first character
+        app.edit_prompt(KeyCode::Home);
+        app.edit_prompt(KeyCode::Backspace);
+        app.edit_prompt(KeyCode::Delete);
+        assert_eq!((app.status.as_str(), app.cursor), ("bé✓", 3));
+        app.edit_prompt(KeyCode::Right);
+        app.edit_prompt(KeyCode::Char('ü'));
+        assert_eq!((app.status.as_str(), app.cursor), ("büé✓", 2));
+        //delete does nothing at the end and backspace takes the last character
+        app.edit_prompt(KeyCode::End);
+        app.edit_prompt(KeyCode::Delete);
+        app.edit_prompt(KeyCode::Backspace);
+        app.edit_prompt(KeyCode::Left);
+        app.edit_prompt(KeyCode::Backspace);
+        assert_eq!((app.status.as_str(), app.cursor), ("bé", 1));
+    }
+
+    #[test]
+    fn paths_complete_inside_a_subdirectory() {
+        let directory = std::env::temp_dir().join(format!("tui_obj_{}_paths", std::process::id()));
+        let models = directory.join("models");
+        fs::create_dir_all(models.join("ünï")).unwrap();
+        for name in ["cube.obj", "cone.stl", "notes.txt", ".hidden.obj"] {
+            fs::write(models.join(name), "").unwrap();
+        }
+        let typed = |rest: &str| format!("{}/{}", models.to_string_lossy(), rest);
+        assert_eq!(path_completions(&typed("")), [typed("ünï/"), typed("cone.stl"), typed("cube.obj")]);
+        assert_eq!(path_completions(&typed("c")), [typed("cone.stl"), typed("cube.obj")]);
+        assert_eq!(path_completions(&typed("ü")), [typed("ünï/")]);
+        assert_eq!(path_completions(&typed(".h")), [typed(".hidden.obj")]);
+
+        let mut app = App::new();
+        app.status_mode = StatusMode::Open;
+        app.status = typed("cu");
+        app.complete();
+        assert_eq!(app.status, typed("cube.obj"));
+        app.status_mode = StatusMode::Command;
+        app.status = format!("open {}", typed("c"));
+        app.complete();
+        assert_eq!(app.status, format!("open {}", typed("c")));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}